@@ -0,0 +1,176 @@
+use crate::config::{merge_json, Config, ConfigError};
+use crate::theme::Theme;
+use std::path::PathBuf;
+
+/// The default number of ticks a headless game runs for.
+const DEFAULT_HEADLESS_TICKS: u64 = 10_000;
+
+/// Shorter names for some of the config flags.
+const FLAG_ALIASES: [(&str, &str); 1] = [("obstacles", "random_obstacle_count")];
+
+/// How the game is presented.
+#[derive(Clone, Debug, Copy, PartialEq)]
+pub enum Mode {
+    /// Play in a window, the default.
+    Window,
+    /// Play in the terminal, e.g. over SSH.
+    Terminal,
+    /// Run the simulation without any output until the snake dies or
+    /// the tick limit is reached, then print the result.
+    Headless,
+}
+
+/// The parsed command line arguments.
+#[derive(Clone, Debug)]
+pub struct Cli {
+    pub config_path: Option<PathBuf>,
+    pub replay_path: Option<PathBuf>,
+    pub mode: Mode,
+    pub ticks: u64,
+    pub should_print_config: bool,
+    pub should_print_help: bool,
+    /// The theme whose colors replace the ones of the config file.
+    pub theme: Option<String>,
+    /// Config values given on the command line, keyed by the config field.
+    pub overrides: serde_json::Map<String, serde_json::Value>,
+}
+
+impl Cli {
+    pub fn parse<I: Iterator<Item = String>>(mut args: I) -> Result<Cli, String> {
+        let config_fields = Config::new().to_json();
+
+        let mut cli = Cli {
+            config_path: None,
+            replay_path: None,
+            mode: Mode::Window,
+            ticks: DEFAULT_HEADLESS_TICKS,
+            should_print_config: false,
+            should_print_help: false,
+            theme: None,
+            overrides: serde_json::Map::new(),
+        };
+
+        while let Some(arg) = args.next() {
+            let flag = match arg.as_str() {
+                "-h" | "--help" => {
+                    cli.should_print_help = true;
+                    continue;
+                }
+                "--print-config" => {
+                    cli.should_print_config = true;
+                    continue;
+                }
+                _ => arg
+                    .strip_prefix("--")
+                    .ok_or_else(|| format!("unexpected argument \"{}\"", arg))?,
+            };
+
+            let value = args
+                .next()
+                .ok_or_else(|| format!("{} expects a value", arg))?;
+
+            match flag {
+                "config" => cli.config_path = Some(PathBuf::from(value)),
+                "replay" => cli.replay_path = Some(PathBuf::from(value)),
+                "theme" => cli.theme = Some(value),
+                "mode" => {
+                    cli.mode = match value.as_str() {
+                        "window" => Mode::Window,
+                        "terminal" => Mode::Terminal,
+                        "headless" => Mode::Headless,
+                        _ => return Err(format!("unknown mode \"{}\"", value)),
+                    }
+                }
+                "ticks" => {
+                    cli.ticks = value
+                        .parse()
+                        .map_err(|_| format!("--ticks expects a number but got \"{}\"", value))?
+                }
+                _ => {
+                    let field = field_of_flag(flag);
+                    if config_fields.get(&field).is_none() {
+                        return Err(format!("unknown option {}", arg));
+                    }
+
+                    // Values are read as json, so arrays like `[1, 0, 0, 1]` work too
+                    let value = serde_json::from_str(value.as_str())
+                        .unwrap_or(serde_json::Value::String(value));
+                    cli.overrides.insert(field, value);
+                }
+            }
+        }
+
+        Ok(cli)
+    }
+
+    /// Applies the config values given on the command line to the config.
+    pub fn apply_overrides(&self, mut config: Config) -> Result<Config, ConfigError> {
+        if let Some(theme) = &self.theme {
+            Theme::load(theme)?.apply(&mut config);
+        }
+
+        if self.overrides.is_empty() {
+            return Ok(config);
+        }
+
+        let mut json = config.to_json();
+        if let serde_json::Value::Object(fields) = &mut json {
+            for (field, value) in self.overrides.iter() {
+                match fields.get_mut(field) {
+                    Some(current) => merge_json(current, value),
+                    None => {
+                        fields.insert(field.clone(), value.clone());
+                    }
+                }
+            }
+        }
+
+        Config::from_json(&json)
+    }
+}
+
+/// Builds the `--help` text, listing a flag for every config field.
+pub fn help() -> String {
+    let mut help = String::from(
+        "A simple customizable snake game.
+
+Usage: snake [OPTIONS]
+
+Options:
+  --config <file>     Use the given config file
+  --replay <file>     Watch a saved replay
+  --mode <mode>       How to run the game: window (default), terminal or headless
+  --ticks <count>     Stop a headless game after this many ticks (default: 10000)
+  --theme <name>      Use the colors of the given theme
+  --print-config      Print the resolved config as JSON and exit
+  -h, --help          Print this help
+
+Config overrides:
+",
+    );
+
+    if let serde_json::Value::Object(fields) = Config::new().to_json() {
+        for (field, default) in fields.iter() {
+            let mut flag = format!("--{} <value>", field.replace('_', "-"));
+            for (alias, aliased_field) in FLAG_ALIASES.iter() {
+                if aliased_field == field {
+                    flag = format!("--{}, {}", alias, flag);
+                }
+            }
+
+            help.push_str(&format!("  {:<46} (default: {})\n", flag, default));
+        }
+    }
+
+    help
+}
+
+fn field_of_flag(flag: &str) -> String {
+    for (alias, field) in FLAG_ALIASES.iter() {
+        if *alias == flag {
+            return String::from(*field);
+        }
+    }
+
+    flag.replace('-', "_")
+}
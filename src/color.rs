@@ -0,0 +1,119 @@
+use crate::config::Config;
+use serde::de::Error;
+use serde::{Deserialize, Deserializer};
+
+pub type Color = [f32; 4];
+
+/// Returns the color of a snake node, where `node_index` counts the nodes
+/// starting from 1 at the tail, so the snake fades from the second color
+/// at the tail into the first color at the head.
+pub fn snake_node_color(
+    config: &Config,
+    is_turbo: bool,
+    node_count: usize,
+    node_index: f32,
+) -> Color {
+    let (snake_first_color, snake_second_color) = match is_turbo {
+        true => (
+            config.snake_turbo_first_color,
+            config.snake_turbo_second_color,
+        ),
+        false => (config.snake_first_color, config.snake_second_color),
+    };
+
+    [
+        lerp(
+            snake_second_color[0],
+            snake_first_color[0],
+            node_count,
+            node_index,
+        ),
+        lerp(
+            snake_second_color[1],
+            snake_first_color[1],
+            node_count,
+            node_index,
+        ),
+        lerp(
+            snake_second_color[2],
+            snake_first_color[2],
+            node_count,
+            node_index,
+        ),
+        lerp(
+            snake_second_color[3],
+            snake_first_color[3],
+            node_count,
+            node_index,
+        ),
+    ]
+}
+
+/// Draws `color` over the opaque `background`, returning an opaque color.
+pub fn blend(color: Color, background: Color) -> Color {
+    let alpha = color[3];
+    [
+        color[0] * alpha + background[0] * (1.0 - alpha),
+        color[1] * alpha + background[1] * (1.0 - alpha),
+        color[2] * alpha + background[2] * (1.0 - alpha),
+        1.0,
+    ]
+}
+
+pub fn lerp(from: f32, to: f32, step_count: usize, current_step: f32) -> f32 {
+    let should_increment = to > from;
+    let current_addition = (f32::abs(from - to) / step_count as f32) * current_step;
+
+    if should_increment {
+        from + current_addition
+    } else {
+        from - current_addition
+    }
+}
+
+/// Parses a hex color like `#7b2cbf`, or `#7b2cbf80` with an alpha channel.
+pub fn parse_hex(hex: &str) -> Option<Color> {
+    let digits = hex.strip_prefix('#')?;
+    if (digits.len() != 6 && digits.len() != 8) || !digits.is_ascii() {
+        return None;
+    }
+
+    let mut color = [1.0; 4];
+    for (index, component) in color.iter_mut().enumerate().take(digits.len() / 2) {
+        let byte = u8::from_str_radix(&digits[index * 2..index * 2 + 2], 16).ok()?;
+        *component = byte as f32 / 255.0;
+    }
+
+    Some(color)
+}
+
+/// Reads a color either as 4 numbers between 0 and 1 or as a hex string.
+pub fn deserialize_color<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Color, D::Error> {
+    match serde_json::Value::deserialize(deserializer)? {
+        serde_json::Value::String(hex) => parse_hex(hex.as_str()).ok_or_else(|| {
+            D::Error::custom(format!(
+                "invalid hex color \"{}\", expected #rrggbb or #rrggbbaa",
+                hex
+            ))
+        }),
+        value => serde_json::from_value(value).map_err(D::Error::custom),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_hex_reads_rgb_and_rgba() {
+        assert_eq!(parse_hex("#ff0080"), Some([1.0, 0.0, 128.0 / 255.0, 1.0]));
+        assert_eq!(parse_hex("#00FF0080"), Some([0.0, 1.0, 0.0, 128.0 / 255.0]));
+    }
+
+    #[test]
+    fn parse_hex_rejects_malformed_colors() {
+        for hex in ["ff0080", "#ff008", "#ff00800", "#gg0080", "#ff0é0", ""] {
+            assert_eq!(parse_hex(hex), None, "{}", hex);
+        }
+    }
+}
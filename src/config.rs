@@ -1,22 +1,551 @@
-use serde_json;
-use std::env::current_exe;
-use std::fs::read_to_string;
+use crate::color::deserialize_color;
+use crate::theme::Theme;
+use serde::{Deserialize, Serialize};
+use std::env::{current_exe, var_os};
+use std::fmt;
+use std::fs::{create_dir_all, read_to_string, write};
+use std::path::{Path, PathBuf};
 
-#[derive(Clone, Debug, Copy)]
+/// The length of the snake at the start of a game.
+pub const INITIAL_SNAKE_LENGTH: i32 = 3;
+
+#[derive(Clone, Debug, Copy, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Config {
     pub screen_w: f64,
     pub screen_h: f64,
     pub cell_w: f64,
     pub random_obstacle_count: u32,
+    pub seed: Option<u64>,
+    /// Whether the window starts in fullscreen mode.
+    pub fullscreen: bool,
+    /// Whether the snake slides between the cells instead of jumping.
+    pub smooth_movement: bool,
+    pub difficulty: Difficulty,
+    /// What happens when the snake reaches the left or the right edge.
+    pub edge_x: EdgeMode,
+    /// What happens when the snake reaches the top or the bottom edge.
+    pub edge_y: EdgeMode,
+    /// The number of moves the snake may make without eating before the
+    /// game ends, or `None` for no limit.
+    pub food_timeout: Option<u64>,
+    pub foods: Foods,
+    pub power_ups: PowerUps,
+    /// Where the bar with the score and the other stats is shown.
+    pub hud_position: HudPosition,
+    /// The height of the HUD bar, which is added to `screen_h`.
+    pub hud_h: f64,
+    pub hud_items: HudItems,
+    #[serde(deserialize_with = "deserialize_color")]
     pub background_color: [f32; 4],
+    #[serde(deserialize_with = "deserialize_color")]
     pub seperator_line_color: [f32; 4],
+    #[serde(deserialize_with = "deserialize_color")]
     pub snake_head_color: [f32; 4],
+    #[serde(deserialize_with = "deserialize_color")]
     pub snake_first_color: [f32; 4],
+    #[serde(deserialize_with = "deserialize_color")]
     pub snake_second_color: [f32; 4],
+    #[serde(deserialize_with = "deserialize_color")]
     pub snake_turbo_first_color: [f32; 4],
+    #[serde(deserialize_with = "deserialize_color")]
     pub snake_turbo_second_color: [f32; 4],
+    #[serde(deserialize_with = "deserialize_color")]
     pub obstacle_color: [f32; 4],
+    #[serde(deserialize_with = "deserialize_color")]
     pub food_color: [f32; 4],
+    #[serde(deserialize_with = "deserialize_color")]
+    pub bonus_food_color: [f32; 4],
+    #[serde(deserialize_with = "deserialize_color")]
+    pub golden_food_color: [f32; 4],
+    #[serde(deserialize_with = "deserialize_color")]
+    pub shrink_food_color: [f32; 4],
+    #[serde(deserialize_with = "deserialize_color")]
+    pub ghost_color: [f32; 4],
+    #[serde(deserialize_with = "deserialize_color")]
+    pub shield_color: [f32; 4],
+    #[serde(deserialize_with = "deserialize_color")]
+    pub slow_motion_color: [f32; 4],
+    #[serde(deserialize_with = "deserialize_color")]
+    pub magnet_color: [f32; 4],
+    #[serde(deserialize_with = "deserialize_color")]
+    pub text_color: [f32; 4],
+    #[serde(deserialize_with = "deserialize_color")]
+    pub text_secondary_color: [f32; 4],
+    #[serde(deserialize_with = "deserialize_color")]
+    pub hud_color: [f32; 4],
+    #[serde(deserialize_with = "deserialize_color")]
+    pub fatal_cell_color: [f32; 4],
+}
+
+/// The shape of the speed ramp.
+#[derive(Clone, Debug, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DifficultyCurve {
+    /// The speed never changes.
+    Constant,
+    /// The delay gets `rate` milliseconds shorter for every point.
+    Linear,
+    /// The delay gets `rate` milliseconds shorter every `step_every` points.
+    Stepped,
+    /// The delay gets `rate` percent shorter for every point.
+    Exponential,
+}
+
+/// What the progress of a game is measured by.
+#[derive(Clone, Debug, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DifficultyMeasure {
+    Score,
+    /// The number of cells the snake has grown by.
+    Length,
+}
+
+/// How the game speeds up as it goes on.
+#[derive(Clone, Debug, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Difficulty {
+    pub curve: DifficultyCurve,
+    pub based_on: DifficultyMeasure,
+    /// The delay between two moves at the start of a game, in milliseconds.
+    pub max_delay: f64,
+    /// The delay the game doesn't get any faster than.
+    pub min_delay: f64,
+    pub rate: f64,
+    /// The number of points per level, and per step of the stepped curve.
+    pub step_every: u32,
+}
+
+impl Difficulty {
+    pub fn new() -> Self {
+        Difficulty {
+            curve: DifficultyCurve::Constant,
+            based_on: DifficultyMeasure::Score,
+            max_delay: 80.0,
+            min_delay: 40.0,
+            rate: 2.0,
+            step_every: 5,
+        }
+    }
+
+    /// Returns the delay between two moves once the given progress is made.
+    pub fn movement_delay(&self, progress: u32) -> f64 {
+        let progress = progress as f64;
+        let delay = match self.curve {
+            DifficultyCurve::Constant => self.max_delay,
+            DifficultyCurve::Linear => self.max_delay - self.rate * progress,
+            DifficultyCurve::Stepped => {
+                let steps = (progress / self.step_every.max(1) as f64).floor();
+                self.max_delay - self.rate * steps
+            }
+            DifficultyCurve::Exponential => {
+                self.max_delay * (1.0 - self.rate / 100.0).powf(progress)
+            }
+        };
+
+        delay.max(self.min_delay).min(self.max_delay)
+    }
+
+    /// Returns the level for the given progress, starting at 1.
+    pub fn level(&self, progress: u32) -> u32 {
+        progress / self.step_every.max(1) + 1
+    }
+}
+
+impl Default for Difficulty {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The rule for the snake reaching an edge of the grid.
+#[derive(Clone, Debug, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EdgeMode {
+    /// The snake comes back in on the opposite edge.
+    Wrap,
+    /// The edge is a solid wall, and running into it ends the game.
+    Wall,
+    /// The snake turns around at the edge, and its tail leads the way back.
+    Bounce,
+}
+
+/// The kinds of food, each with its own rules and color.
+#[derive(Clone, Debug, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FoodType {
+    Normal,
+    Bonus,
+    Golden,
+    Shrink,
+}
+
+pub const FOOD_TYPES: [FoodType; 4] = [
+    FoodType::Normal,
+    FoodType::Bonus,
+    FoodType::Golden,
+    FoodType::Shrink,
+];
+
+impl FoodType {
+    /// Returns the key of the kind in the `foods` config.
+    pub fn name(&self) -> &'static str {
+        match self {
+            FoodType::Normal => "normal",
+            FoodType::Bonus => "bonus",
+            FoodType::Golden => "golden",
+            FoodType::Shrink => "shrink",
+        }
+    }
+}
+
+/// The most points a single food can be worth, so doubling them with
+/// turbo still fits the score.
+pub const MAX_POINTS: u16 = u16::MAX / 2;
+
+/// The rules of a single kind of food.
+#[derive(Clone, Debug, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FoodKind {
+    /// The points eating it is worth, doubled with turbo.
+    pub points: u16,
+    /// The number of cells the snake grows by, or loses from its tail
+    /// when negative.
+    pub growth: i32,
+    /// How likely this kind is picked for a new food, relative to the
+    /// weights of the other kinds.
+    pub weight: u32,
+    /// The most food of this kind on the board at once.
+    pub max_count: u32,
+}
+
+impl Default for FoodKind {
+    fn default() -> Self {
+        FoodKind {
+            points: 1,
+            growth: 1,
+            weight: 1,
+            max_count: 0,
+        }
+    }
+}
+
+/// The food on the board. Only normal food is placed by default, the
+/// other kinds have to be turned on with a `max_count`.
+#[derive(Clone, Debug, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Foods {
+    /// The number of food items kept on the board.
+    pub count: u32,
+    pub normal: FoodKind,
+    pub bonus: FoodKind,
+    pub golden: FoodKind,
+    pub shrink: FoodKind,
+    pub expiring: ExpiringFood,
+}
+
+/// A bonus food that shows up now and then on top of the other food, and
+/// disappears again if it isn't eaten in time. It is drawn and grows the
+/// snake like bonus food, but its points depend on how fast it was eaten.
+#[derive(Clone, Debug, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExpiringFood {
+    /// The chance of it showing up on every move while there is none,
+    /// between 0 and 1.
+    pub spawn_chance: f64,
+    /// The number of moves it stays on the board.
+    pub lifetime: u64,
+    /// The points when eaten right after showing up.
+    pub max_points: u16,
+    /// The points when eaten on the last move before it disappears.
+    pub min_points: u16,
+}
+
+impl ExpiringFood {
+    pub fn new() -> Self {
+        ExpiringFood {
+            spawn_chance: 0.0,
+            lifetime: 40,
+            max_points: 10,
+            min_points: 2,
+        }
+    }
+
+    /// Returns the points for eating it with the given number of moves left.
+    pub fn points(&self, moves_left: u64) -> u16 {
+        let left = moves_left.min(self.lifetime) as f64 / self.lifetime.max(1) as f64;
+        let range = self.max_points.saturating_sub(self.min_points) as f64;
+        self.min_points + (range * left).round() as u16
+    }
+}
+
+impl Default for ExpiringFood {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Foods {
+    pub fn new() -> Self {
+        Foods {
+            count: 1,
+            normal: FoodKind {
+                points: 1,
+                growth: 1,
+                weight: 10,
+                max_count: 1,
+            },
+            bonus: FoodKind {
+                points: 3,
+                growth: 1,
+                weight: 3,
+                max_count: 0,
+            },
+            golden: FoodKind {
+                points: 10,
+                growth: 2,
+                weight: 1,
+                max_count: 0,
+            },
+            shrink: FoodKind {
+                points: 0,
+                growth: -3,
+                weight: 2,
+                max_count: 0,
+            },
+            expiring: ExpiringFood::new(),
+        }
+    }
+
+    pub fn kind(&self, food_type: FoodType) -> &FoodKind {
+        match food_type {
+            FoodType::Normal => &self.normal,
+            FoodType::Bonus => &self.bonus,
+            FoodType::Golden => &self.golden,
+            FoodType::Shrink => &self.shrink,
+        }
+    }
+
+    pub fn kind_mut(&mut self, food_type: FoodType) -> &mut FoodKind {
+        match food_type {
+            FoodType::Normal => &mut self.normal,
+            FoodType::Bonus => &mut self.bonus,
+            FoodType::Golden => &mut self.golden,
+            FoodType::Shrink => &mut self.shrink,
+        }
+    }
+}
+
+impl Default for Foods {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The pickups that give the snake a timed effect.
+#[derive(Clone, Debug, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PowerUpType {
+    /// The snake can pass through its own body.
+    Ghost,
+    /// The snake survives a single obstacle hit, which breaks the obstacle.
+    Shield,
+    /// The snake moves at half the speed.
+    SlowMotion,
+    /// The food close to the head moves towards it.
+    Magnet,
+}
+
+pub const POWER_UP_TYPES: [PowerUpType; 4] = [
+    PowerUpType::Ghost,
+    PowerUpType::Shield,
+    PowerUpType::SlowMotion,
+    PowerUpType::Magnet,
+];
+
+impl PowerUpType {
+    /// Returns the key of the kind in the `power_ups` config.
+    pub fn name(&self) -> &'static str {
+        match self {
+            PowerUpType::Ghost => "ghost",
+            PowerUpType::Shield => "shield",
+            PowerUpType::SlowMotion => "slow_motion",
+            PowerUpType::Magnet => "magnet",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            PowerUpType::Ghost => "Ghost",
+            PowerUpType::Shield => "Shield",
+            PowerUpType::SlowMotion => "Slow",
+            PowerUpType::Magnet => "Magnet",
+        }
+    }
+}
+
+/// The rules of a single kind of power-up.
+#[derive(Clone, Debug, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PowerUpKind {
+    pub enabled: bool,
+    /// How likely this kind is picked for a new pickup, relative to the
+    /// weights of the other kinds.
+    pub weight: u32,
+    /// The number of moves the effect lasts for.
+    pub duration: u64,
+}
+
+impl Default for PowerUpKind {
+    fn default() -> Self {
+        PowerUpKind {
+            enabled: true,
+            weight: 1,
+            duration: 30,
+        }
+    }
+}
+
+/// The pickups placed on the board now and then, one at a time.
+#[derive(Clone, Debug, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PowerUps {
+    /// The chance of a pickup showing up on every move while there is
+    /// none, between 0 and 1.
+    pub spawn_chance: f64,
+    /// The number of moves a pickup stays on the board.
+    pub lifetime: u64,
+    pub ghost: PowerUpKind,
+    pub shield: PowerUpKind,
+    pub slow_motion: PowerUpKind,
+    pub magnet: PowerUpKind,
+}
+
+impl PowerUps {
+    pub fn new() -> Self {
+        PowerUps {
+            spawn_chance: 0.0,
+            lifetime: 50,
+            ghost: PowerUpKind {
+                enabled: true,
+                weight: 2,
+                duration: 30,
+            },
+            shield: PowerUpKind {
+                enabled: true,
+                weight: 2,
+                duration: 100,
+            },
+            slow_motion: PowerUpKind {
+                enabled: true,
+                weight: 1,
+                duration: 40,
+            },
+            magnet: PowerUpKind {
+                enabled: true,
+                weight: 1,
+                duration: 40,
+            },
+        }
+    }
+
+    pub fn kind(&self, power_up_type: PowerUpType) -> &PowerUpKind {
+        match power_up_type {
+            PowerUpType::Ghost => &self.ghost,
+            PowerUpType::Shield => &self.shield,
+            PowerUpType::SlowMotion => &self.slow_motion,
+            PowerUpType::Magnet => &self.magnet,
+        }
+    }
+
+    pub fn kind_mut(&mut self, power_up_type: PowerUpType) -> &mut PowerUpKind {
+        match power_up_type {
+            PowerUpType::Ghost => &mut self.ghost,
+            PowerUpType::Shield => &mut self.shield,
+            PowerUpType::SlowMotion => &mut self.slow_motion,
+            PowerUpType::Magnet => &mut self.magnet,
+        }
+    }
+}
+
+impl Default for PowerUps {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The place of the HUD bar, outside of the playfield.
+#[derive(Clone, Debug, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HudPosition {
+    Top,
+    Bottom,
+    Hidden,
+}
+
+/// The stats shown in the HUD bar, from left to right.
+#[derive(Clone, Debug, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HudItems {
+    pub score: bool,
+    pub high_score: bool,
+    pub length: bool,
+    pub time: bool,
+    pub speed: bool,
+    pub turbo: bool,
+    pub level: bool,
+    /// The moves left of every active power-up effect.
+    pub power_ups: bool,
+}
+
+impl HudItems {
+    pub fn new() -> Self {
+        HudItems {
+            score: true,
+            high_score: true,
+            length: true,
+            time: true,
+            speed: true,
+            turbo: true,
+            level: true,
+            power_ups: true,
+        }
+    }
+
+    /// Returns every item along with its field name.
+    pub fn items(&self) -> [(&'static str, bool); 8] {
+        [
+            ("score", self.score),
+            ("high_score", self.high_score),
+            ("length", self.length),
+            ("time", self.time),
+            ("speed", self.speed),
+            ("turbo", self.turbo),
+            ("level", self.level),
+            ("power_ups", self.power_ups),
+        ]
+    }
+
+    /// Returns the item with the given field name.
+    pub fn item_mut(&mut self, name: &str) -> Option<&mut bool> {
+        match name {
+            "score" => Some(&mut self.score),
+            "high_score" => Some(&mut self.high_score),
+            "length" => Some(&mut self.length),
+            "time" => Some(&mut self.time),
+            "speed" => Some(&mut self.speed),
+            "turbo" => Some(&mut self.turbo),
+            "level" => Some(&mut self.level),
+            "power_ups" => Some(&mut self.power_ups),
+            _ => None,
+        }
+    }
+}
+
+impl Default for HudItems {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Config {
@@ -27,6 +556,18 @@ impl Config {
             screen_h: 600.0,
             cell_w: 25.0,
             random_obstacle_count: 10,
+            seed: None,
+            fullscreen: false,
+            smooth_movement: false,
+            difficulty: Difficulty::new(),
+            edge_x: EdgeMode::Wrap,
+            edge_y: EdgeMode::Wrap,
+            food_timeout: None,
+            foods: Foods::new(),
+            power_ups: PowerUps::new(),
+            hud_position: HudPosition::Top,
+            hud_h: 40.0,
+            hud_items: HudItems::new(),
             background_color: [0.321, 0.133, 0.403, 1.0],
             seperator_line_color: [0.0, 0.0, 0.0, 1.0],
             snake_head_color: [0.0, 0.0, 0.0, 1.0],
@@ -36,132 +577,753 @@ impl Config {
             snake_turbo_second_color: [100.0 / 225.0, 181.0 / 255.0, 246.0 / 255.0, 1.0],
             obstacle_color: [16.0 / 255.0, 0.0, 43.0 / 255.0, 1.0],
             food_color: [199.0 / 255.0, 125.0 / 255.0, 1.0, 1.0],
+            bonus_food_color: [1.0, 0.6, 0.2, 1.0],
+            golden_food_color: [1.0, 0.843, 0.0, 1.0],
+            shrink_food_color: [0.298, 0.686, 0.314, 1.0],
+            ghost_color: [0.878, 0.878, 0.878, 1.0],
+            shield_color: [0.259, 0.647, 0.961, 1.0],
+            slow_motion_color: [0.149, 0.776, 0.855, 1.0],
+            magnet_color: [0.898, 0.224, 0.208, 1.0],
+            text_color: [123.0 / 255.0, 44.0 / 255.0, 191.0 / 255.0, 1.0],
+            text_secondary_color: [199.0 / 255.0, 125.0 / 255.0, 1.0, 1.0],
+            hud_color: [0.239, 0.098, 0.301, 1.0],
+            fatal_cell_color: [0.937, 0.278, 0.435, 1.0],
         }
     }
 
-    pub fn load_config_file() -> Result<Config, Box<dyn std::error::Error>> {
-        let mut config = Self::new();
+    /// The number of cells that fit horizontally on the screen.
+    pub fn grid_w(&self) -> i32 {
+        (self.screen_w / self.cell_w) as i32
+    }
 
-        let mut config_path = current_exe().unwrap();
-        config_path.pop();
-        config_path.push("snake_config.json");
+    /// The number of cells that fit vertically on the screen.
+    pub fn grid_h(&self) -> i32 {
+        (self.screen_h / self.cell_w) as i32
+    }
 
-        match read_to_string(config_path) {
-            Ok(file_content) => {
-                let json: serde_json::Value = serde_json::from_str(file_content.as_str())?;
+    /// Returns the color of the given kind of food.
+    pub fn color_of_food(&self, food_type: FoodType) -> [f32; 4] {
+        match food_type {
+            FoodType::Normal => self.food_color,
+            FoodType::Bonus => self.bonus_food_color,
+            FoodType::Golden => self.golden_food_color,
+            FoodType::Shrink => self.shrink_food_color,
+        }
+    }
 
-                if let Some(value) = json.get("screen_w") {
-                    if let serde_json::Value::Number(screen_w) = value {
-                        config.screen_w = screen_w.as_f64().expect("screen_w should be a f64");
-                    }
-                }
+    /// Returns the color of the given kind of power-up.
+    pub fn color_of_power_up(&self, power_up_type: PowerUpType) -> [f32; 4] {
+        match power_up_type {
+            PowerUpType::Ghost => self.ghost_color,
+            PowerUpType::Shield => self.shield_color,
+            PowerUpType::SlowMotion => self.slow_motion_color,
+            PowerUpType::Magnet => self.magnet_color,
+        }
+    }
 
-                if let Some(value) = json.get("screen_h") {
-                    if let serde_json::Value::Number(screen_h) = value {
-                        config.screen_h = screen_h.as_f64().expect("screen_h should be a f64");
-                    }
-                }
+    /// The height of the HUD bar, or 0 if it is hidden.
+    pub fn hud_height(&self) -> f64 {
+        match self.hud_position {
+            HudPosition::Hidden => 0.0,
+            _ => self.hud_h,
+        }
+    }
 
-                if let Some(value) = json.get("cell_w") {
-                    if let serde_json::Value::Number(cell_w) = value {
-                        config.cell_w = cell_w.as_f64().expect("cell_w should be a f64");
-                    }
-                }
+    /// The height of the whole window content, the playfield and the HUD.
+    pub fn canvas_h(&self) -> f64 {
+        self.screen_h + self.hud_height()
+    }
 
-                if let Some(value) = json.get("random_obstacle_count") {
-                    if let serde_json::Value::Number(random_obstacle_count) = value {
-                        config.random_obstacle_count = random_obstacle_count
-                            .as_u64()
-                            .expect("random obstacle_count should be a u64")
-                            as u32;
-                    }
-                }
+    /// The distance from the top of the canvas to the playfield.
+    pub fn board_y(&self) -> f64 {
+        match self.hud_position {
+            HudPosition::Top => self.hud_h,
+            _ => 0.0,
+        }
+    }
 
-                if let Some(value) = json.get("background_color") {
-                    if let serde_json::Value::Array(background_color) = value {
-                        config.background_color = vec_to_arr(background_color);
-                    }
-                }
+    /// Loads the config file found by `find_config_file`, or the default
+    /// config if there is none.
+    pub fn load_config_file(explicit_path: Option<PathBuf>) -> Result<Config, ConfigError> {
+        let config_path = match Self::find_config_file(explicit_path) {
+            Some(config_path) => config_path,
+            None => return Ok(Self::new()), // Just return the default config
+        };
 
-                if let Some(value) = json.get("seperator_line_color") {
-                    if let serde_json::Value::Array(seperator_line_color) = value {
-                        config.seperator_line_color = vec_to_arr(seperator_line_color);
-                    }
-                }
+        let with_file = |mut e: ConfigError| {
+            e.file = Some(config_path.clone());
+            e
+        };
 
-                if let Some(value) = json.get("snake_head_color") {
-                    if let serde_json::Value::Array(snake_head_color) = value {
-                        config.snake_head_color = vec_to_arr(snake_head_color);
-                    }
-                }
+        let file_content = read_to_string(&config_path)
+            .map_err(|e| with_file(ConfigError::new(vec![FieldError::new("$", e.to_string())])))?;
 
-                if let Some(value) = json.get("snake_first_color") {
-                    if let serde_json::Value::Array(snake_first_color) = value {
-                        config.snake_first_color = vec_to_arr(snake_first_color);
-                    }
-                }
+        Self::from_str(file_content.as_str()).map_err(with_file)
+    }
 
-                if let Some(value) = json.get("snake_second_color") {
-                    if let serde_json::Value::Array(snake_second_color) = value {
-                        config.snake_second_color = vec_to_arr(snake_second_color);
-                    }
-                }
+    /// Looks for the config file in the following order: the given path,
+    /// the `SNAKE_CONFIG` env var, `$XDG_CONFIG_HOME/snake/config.json` and
+    /// `snake_config.json` next to the executable.
+    ///
+    /// Explicitly given paths are returned even if they don't exist, so
+    /// the error can be reported instead of silently using the defaults.
+    pub fn find_config_file(explicit_path: Option<PathBuf>) -> Option<PathBuf> {
+        if explicit_path.is_some() {
+            return explicit_path;
+        }
 
-                if let Some(value) = json.get("snake_turbo_first_color") {
-                    if let serde_json::Value::Array(snake_turbo_first_color) = value {
-                        config.snake_turbo_first_color = vec_to_arr(snake_turbo_first_color);
-                    }
+        if let Some(env_path) = var_os("SNAKE_CONFIG") {
+            return Some(PathBuf::from(env_path));
+        }
+
+        if let Some(mut xdg_path) = config_dir() {
+            xdg_path.push("config.json");
+            if xdg_path.is_file() {
+                return Some(xdg_path);
+            }
+        }
+
+        let exe_path = Self::default_path()?;
+        if exe_path.is_file() {
+            return Some(exe_path);
+        }
+
+        None
+    }
+
+    /// Returns the path of `snake_config.json` next to the executable.
+    pub fn default_path() -> Option<PathBuf> {
+        let mut exe_path = current_exe().ok()?;
+        exe_path.pop();
+        exe_path.push("snake_config.json");
+        Some(exe_path)
+    }
+
+    /// Writes the config to the given file. The keys of an existing file
+    /// that aren't part of the config are kept as they are.
+    pub fn save_config_file(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        let mut object = match read_to_string(path)
+            .ok()
+            .and_then(|content| serde_json::from_str::<serde_json::Value>(&content).ok())
+        {
+            Some(serde_json::Value::Object(object)) => object,
+            _ => serde_json::Map::new(),
+        };
+
+        if let serde_json::Value::Object(fields) = self.to_json() {
+            object.extend(fields);
+        }
+
+        // Leave out the colors that match the theme of the file, so
+        // switching the theme there keeps working
+        if let Some(Ok(theme)) = object
+            .get("theme")
+            .and_then(|name| name.as_str())
+            .map(Theme::load)
+        {
+            let mut themed = *self;
+            theme.apply(&mut themed);
+
+            for ((name, color), (_, theme_color)) in self.colors().iter().zip(themed.colors()) {
+                if *color == theme_color {
+                    object.remove(*name);
                 }
+            }
+        }
+
+        if let Some(parent) = path.parent() {
+            create_dir_all(parent)?;
+        }
+        write(
+            path,
+            serde_json::to_string_pretty(&serde_json::Value::Object(object))?,
+        )?;
+        Ok(())
+    }
+
+    /// Parses and validates a config from json text.
+    pub fn from_str(content: &str) -> Result<Config, ConfigError> {
+        match serde_json::from_str::<serde_json::Value>(content) {
+            Ok(json) => Self::from_json(&json),
+            Err(e) => Err(ConfigError::new(vec![FieldError::new("$", e.to_string())])),
+        }
+    }
+
+    /// Builds a config from the given json, using the default values
+    /// for the missing keys. Every invalid field is reported in the error.
+    pub fn from_json(json: &serde_json::Value) -> Result<Config, ConfigError> {
+        let object = json.as_object().ok_or_else(|| {
+            ConfigError::new(vec![FieldError::new("$", "expected a json object")])
+        })?;
+
+        let defaults = match serde_json::to_value(Self::new()) {
+            Ok(serde_json::Value::Object(defaults)) => defaults,
+            _ => unreachable!("the config always serializes to an object"),
+        };
+
+        // Check every key on its own, so all the invalid ones can be listed
+        let mut merged = serde_json::Value::Object(defaults.clone());
+        let mut errors = Vec::new();
+        for (key, value) in object {
+            // The theme is not a field, it only provides the colors
+            if key == "theme" {
+                continue;
+            }
+
+            check_field(&defaults, &mut merged, &[key], value, &mut errors);
+        }
+
+        let mut config: Config = serde_json::from_value(merged)
+            .map_err(|e| ConfigError::new(vec![FieldError::new("$", e.to_string())]))?;
+
+        // Colors set in the config win over the ones of the theme
+        match object.get("theme") {
+            None | Some(serde_json::Value::Null) => {}
+            Some(serde_json::Value::String(name)) => match Theme::load(name) {
+                Ok(theme) => {
+                    let mut themed = config;
+                    theme.apply(&mut themed);
 
-                if let Some(value) = json.get("snake_turbo_second_color") {
-                    if let serde_json::Value::Array(snake_turbo_second_color) = value {
-                        config.snake_turbo_second_color = vec_to_arr(snake_turbo_second_color);
+                    for (name, color) in themed.colors() {
+                        if !object.contains_key(name) {
+                            if let Some(config_color) = config.color_mut(name) {
+                                *config_color = color;
+                            }
+                        }
                     }
                 }
+                Err(e) => errors.extend(e.errors),
+            },
+            Some(_) => errors.push(FieldError::new("$.theme", "expected the name of a theme")),
+        }
 
-                if let Some(value) = json.get("obstacle_color") {
-                    if let serde_json::Value::Array(obstacle_color) = value {
-                        config.obstacle_color = vec_to_arr(obstacle_color);
-                    }
+        // Validate the values that could be read, skipping the already reported ones
+        for error in config.validate() {
+            if !errors.iter().any(|reported| reported.path == error.path) {
+                errors.push(error);
+            }
+        }
+
+        if !errors.is_empty() {
+            return Err(ConfigError::new(errors));
+        }
+
+        Ok(config)
+    }
+
+    pub fn to_json(self) -> serde_json::Value {
+        // Go through the text form, so the f32 colors keep their short form
+        let text = serde_json::to_string(&self).expect("the config should be serializable");
+        serde_json::from_str(text.as_str()).expect("the config should be valid json")
+    }
+
+    /// Checks that the values make a playable game.
+    pub fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+
+        if self.cell_w <= 0.0 || !self.cell_w.is_finite() {
+            errors.push(FieldError::new("$.cell_w", "should be greater than 0"));
+            return errors;
+        }
+
+        for (path, size) in [("$.screen_w", self.screen_w), ("$.screen_h", self.screen_h)] {
+            if size <= 0.0 || !size.is_finite() {
+                errors.push(FieldError::new(path, "should be greater than 0"));
+            } else if (size / self.cell_w).fract() != 0.0 {
+                errors.push(FieldError::new(
+                    path,
+                    format!("should be a multiple of cell_w ({})", self.cell_w),
+                ));
+            }
+        }
+
+        if self.hud_position != HudPosition::Hidden
+            && (self.hud_h <= 0.0 || !self.hud_h.is_finite())
+        {
+            errors.push(FieldError::new("$.hud_h", "should be greater than 0"));
+        }
+
+        let difficulty = &self.difficulty;
+        if difficulty.min_delay <= 0.0 || !difficulty.min_delay.is_finite() {
+            errors.push(FieldError::new(
+                "$.difficulty.min_delay",
+                "should be greater than 0",
+            ));
+        } else if difficulty.max_delay < difficulty.min_delay || !difficulty.max_delay.is_finite() {
+            errors.push(FieldError::new(
+                "$.difficulty.max_delay",
+                "should be at least as large as min_delay",
+            ));
+        }
+
+        if difficulty.rate < 0.0 || !difficulty.rate.is_finite() {
+            errors.push(FieldError::new(
+                "$.difficulty.rate",
+                "should be 0 or greater",
+            ));
+        } else if difficulty.curve == DifficultyCurve::Exponential && difficulty.rate >= 100.0 {
+            errors.push(FieldError::new(
+                "$.difficulty.rate",
+                "should be below 100 for the exponential curve",
+            ));
+        }
+
+        if difficulty.step_every == 0 {
+            errors.push(FieldError::new(
+                "$.difficulty.step_every",
+                "should be at least 1",
+            ));
+        }
+
+        if self.foods.count == 0 {
+            errors.push(FieldError::new("$.foods.count", "should be at least 1"));
+        } else if !FOOD_TYPES.iter().any(|food_type| {
+            let kind = self.foods.kind(*food_type);
+            kind.max_count > 0 && kind.weight > 0
+        }) {
+            errors.push(FieldError::new(
+                "$.foods",
+                "at least one kind of food should have a max_count and a weight above 0",
+            ));
+        }
+
+        let expiring = &self.foods.expiring;
+        if !(0.0..=1.0).contains(&expiring.spawn_chance) {
+            errors.push(FieldError::new(
+                "$.foods.expiring.spawn_chance",
+                "should be between 0 and 1",
+            ));
+        }
+
+        if expiring.lifetime == 0 {
+            errors.push(FieldError::new(
+                "$.foods.expiring.lifetime",
+                "should be at least 1",
+            ));
+        }
+
+        for food_type in FOOD_TYPES {
+            if self.foods.kind(food_type).points > MAX_POINTS {
+                errors.push(FieldError::new(
+                    &format!("$.foods.{}.points", food_type.name()),
+                    format!("should be at most {}", MAX_POINTS),
+                ));
+            }
+        }
+
+        if expiring.max_points > MAX_POINTS {
+            errors.push(FieldError::new(
+                "$.foods.expiring.max_points",
+                format!("should be at most {}", MAX_POINTS),
+            ));
+        } else if expiring.max_points < expiring.min_points {
+            errors.push(FieldError::new(
+                "$.foods.expiring.max_points",
+                "should be at least as large as min_points",
+            ));
+        }
+
+        let power_ups = &self.power_ups;
+        if !(0.0..=1.0).contains(&power_ups.spawn_chance) {
+            errors.push(FieldError::new(
+                "$.power_ups.spawn_chance",
+                "should be between 0 and 1",
+            ));
+        }
+
+        if power_ups.lifetime == 0 {
+            errors.push(FieldError::new(
+                "$.power_ups.lifetime",
+                "should be at least 1",
+            ));
+        }
+
+        for power_up_type in POWER_UP_TYPES {
+            if power_ups.kind(power_up_type).duration == 0 {
+                errors.push(FieldError::new(
+                    &format!("$.power_ups.{}.duration", power_up_type.name()),
+                    "should be at least 1",
+                ));
+            }
+        }
+
+        if self.food_timeout == Some(0) {
+            errors.push(FieldError::new("$.food_timeout", "should be at least 1"));
+        }
+
+        if self.grid_w() < INITIAL_SNAKE_LENGTH || self.grid_h() < 1 {
+            errors.push(FieldError::new(
+                "$.cell_w",
+                format!(
+                    "the grid should be at least {} cells wide and 1 cell high",
+                    INITIAL_SNAKE_LENGTH
+                ),
+            ));
+        } else {
+            // Leave room for the snake and the food
+            let cell_count = self.grid_w() as i64 * self.grid_h() as i64;
+            let room = (cell_count - INITIAL_SNAKE_LENGTH as i64 - 1).max(0);
+            if self.random_obstacle_count as i64 > room {
+                errors.push(FieldError::new(
+                    "$.random_obstacle_count",
+                    format!("the grid only has room for {} obstacles", room),
+                ));
+            }
+        }
+
+        for (name, color) in self.colors() {
+            for (index, component) in color.iter().enumerate() {
+                if !(0.0..=1.0).contains(component) {
+                    errors.push(FieldError::new(
+                        &format!("$.{}[{}]", name, index),
+                        "should be between 0 and 1",
+                    ));
                 }
+            }
+        }
+
+        errors
+    }
+
+    /// Returns every color of the config along with its field name.
+    pub fn colors(&self) -> [(&'static str, [f32; 4]); 20] {
+        [
+            ("background_color", self.background_color),
+            ("seperator_line_color", self.seperator_line_color),
+            ("snake_head_color", self.snake_head_color),
+            ("snake_first_color", self.snake_first_color),
+            ("snake_second_color", self.snake_second_color),
+            ("snake_turbo_first_color", self.snake_turbo_first_color),
+            ("snake_turbo_second_color", self.snake_turbo_second_color),
+            ("obstacle_color", self.obstacle_color),
+            ("food_color", self.food_color),
+            ("bonus_food_color", self.bonus_food_color),
+            ("golden_food_color", self.golden_food_color),
+            ("shrink_food_color", self.shrink_food_color),
+            ("ghost_color", self.ghost_color),
+            ("shield_color", self.shield_color),
+            ("slow_motion_color", self.slow_motion_color),
+            ("magnet_color", self.magnet_color),
+            ("text_color", self.text_color),
+            ("text_secondary_color", self.text_secondary_color),
+            ("hud_color", self.hud_color),
+            ("fatal_cell_color", self.fatal_cell_color),
+        ]
+    }
+
+    /// Returns the color with the given field name.
+    pub fn color_mut(&mut self, name: &str) -> Option<&mut [f32; 4]> {
+        match name {
+            "background_color" => Some(&mut self.background_color),
+            "seperator_line_color" => Some(&mut self.seperator_line_color),
+            "snake_head_color" => Some(&mut self.snake_head_color),
+            "snake_first_color" => Some(&mut self.snake_first_color),
+            "snake_second_color" => Some(&mut self.snake_second_color),
+            "snake_turbo_first_color" => Some(&mut self.snake_turbo_first_color),
+            "snake_turbo_second_color" => Some(&mut self.snake_turbo_second_color),
+            "obstacle_color" => Some(&mut self.obstacle_color),
+            "food_color" => Some(&mut self.food_color),
+            "bonus_food_color" => Some(&mut self.bonus_food_color),
+            "golden_food_color" => Some(&mut self.golden_food_color),
+            "shrink_food_color" => Some(&mut self.shrink_food_color),
+            "ghost_color" => Some(&mut self.ghost_color),
+            "shield_color" => Some(&mut self.shield_color),
+            "slow_motion_color" => Some(&mut self.slow_motion_color),
+            "magnet_color" => Some(&mut self.magnet_color),
+            "text_color" => Some(&mut self.text_color),
+            "text_secondary_color" => Some(&mut self.text_secondary_color),
+            "hud_color" => Some(&mut self.hud_color),
+            "fatal_cell_color" => Some(&mut self.fatal_cell_color),
+            _ => None,
+        }
+    }
+}
 
-                if let Some(value) = json.get("food_color") {
-                    if let serde_json::Value::Array(food_color) = value {
-                        config.food_color = vec_to_arr(food_color);
+/// Returns `$XDG_CONFIG_HOME/snake`, or `~/.config/snake` when it is not set.
+pub fn config_dir() -> Option<PathBuf> {
+    let xdg_config_home = match var_os("XDG_CONFIG_HOME") {
+        Some(config_home) => Some(PathBuf::from(config_home)),
+        None => var_os("HOME").map(|home| PathBuf::from(home).join(".config")),
+    };
+
+    xdg_config_home.map(|config_home| config_home.join("snake"))
+}
+
+/// Returns `$XDG_DATA_HOME/snake`, or `~/.local/share/snake` when it is not set.
+pub fn data_dir() -> Option<PathBuf> {
+    let xdg_data_home = match var_os("XDG_DATA_HOME") {
+        Some(data_home) => Some(PathBuf::from(data_home)),
+        None => var_os("HOME").map(|home| PathBuf::from(home).join(".local").join("share")),
+    };
+
+    xdg_data_home.map(|data_home| data_home.join("snake"))
+}
+
+/// Checks the value of the field at `path` on its own and merges it into
+/// `merged` when it can be read. Objects are checked key by key, so the
+/// errors and the unknown keys point at the exact value, e.g.
+/// `$.foods.bonus.points`, and the keys they leave out keep their defaults.
+fn check_field(
+    defaults: &serde_json::Map<String, serde_json::Value>,
+    merged: &mut serde_json::Value,
+    path: &[&str],
+    value: &serde_json::Value,
+    errors: &mut Vec<FieldError>,
+) {
+    let mut default = defaults.get(path[0]);
+    for key in &path[1..] {
+        default = default.and_then(|default| default.get(key));
+    }
+
+    match (default, value) {
+        (None, _) => eprintln!("Ignoring unknown config key \"{}\"", path.join(".")),
+        (Some(serde_json::Value::Object(_)), serde_json::Value::Object(fields)) => {
+            for (key, field) in fields {
+                let mut field_path = path.to_vec();
+                field_path.push(key);
+                check_field(defaults, merged, &field_path, field, errors);
+            }
+        }
+        (Some(_), _) => {
+            // Wrap the value in the objects leading to it
+            let mut patch = value.clone();
+            for key in path.iter().rev() {
+                let mut object = serde_json::Map::new();
+                object.insert(key.to_string(), patch);
+                patch = serde_json::Value::Object(object);
+            }
+
+            let mut single = serde_json::Value::Object(defaults.clone());
+            merge_json(&mut single, &patch);
+
+            match serde_json::from_value::<Config>(single) {
+                Ok(_) => merge_json(merged, &patch),
+                Err(e) => errors.push(FieldError::new(
+                    &format!("$.{}", path.join(".")),
+                    e.to_string(),
+                )),
+            }
+        }
+    }
+}
+
+/// Copies the values of `patch` into `base`, merging the objects found
+/// in both instead of replacing them.
+pub fn merge_json(base: &mut serde_json::Value, patch: &serde_json::Value) {
+    match (base, patch) {
+        (serde_json::Value::Object(base), serde_json::Value::Object(patch)) => {
+            for (key, value) in patch {
+                match base.get_mut(key) {
+                    Some(base_value) => merge_json(base_value, value),
+                    None => {
+                        base.insert(key.clone(), value.clone());
                     }
                 }
             }
-            Err(_) => return Ok(config), // Just return the default config
         }
+        (base, patch) => *base = patch.clone(),
+    }
+}
 
-        Ok(config)
+impl Default for Config {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A problem with a single value of the config.
+#[derive(Clone, Debug)]
+pub struct FieldError {
+    /// The json path of the value, e.g. `$.background_color[2]`.
+    pub path: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new<M: Into<String>>(path: &str, message: M) -> Self {
+        FieldError {
+            path: String::from(path),
+            message: message.into(),
+        }
+    }
+}
+
+/// Every problem found while loading a config.
+#[derive(Clone, Debug)]
+pub struct ConfigError {
+    pub file: Option<PathBuf>,
+    pub errors: Vec<FieldError>,
+}
+
+impl ConfigError {
+    pub fn new(errors: Vec<FieldError>) -> Self {
+        ConfigError { file: None, errors }
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.file {
+            Some(file) => write!(f, "Invalid config file {}:", file.display())?,
+            None => write!(f, "Invalid config:")?,
+        }
+
+        for error in self.errors.iter() {
+            write!(f, "\n  {}: {}", error.path, error.message)?;
+        }
+
+        Ok(())
     }
 }
 
-fn vec_to_arr(vec: &Vec<serde_json::Value>) -> [f32; 4] {
-    if vec.len() < 4 {
-        eprintln!(
-            "ExpecteConfig d vector to have at least 4 elements but it had {}",
-            vec.len()
+impl std::error::Error for ConfigError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error_paths(json: &str) -> Vec<String> {
+        match Config::from_str(json) {
+            Ok(_) => Vec::new(),
+            Err(e) => e.errors.into_iter().map(|error| error.path).collect(),
+        }
+    }
+
+    #[test]
+    fn the_default_config_is_valid() {
+        assert!(Config::new().validate().is_empty());
+        assert!(Config::from_str("{}").is_ok());
+    }
+
+    #[test]
+    fn invalid_json_is_reported_at_the_root() {
+        assert_eq!(error_paths("{"), ["$"]);
+        assert_eq!(error_paths("[1, 2]"), ["$"]);
+    }
+
+    #[test]
+    fn every_invalid_key_is_reported() {
+        let paths = error_paths(r#"{ "screen_w": "wide", "fullscreen": 1, "cell_w": 25 }"#);
+
+        assert_eq!(paths, ["$.fullscreen", "$.screen_w"]);
+    }
+
+    #[test]
+    fn nested_errors_point_at_the_value() {
+        let paths = error_paths(
+            r#"{
+                "foods": { "bonus": { "points": "many" } },
+                "difficulty": { "curve": "steep" },
+                "hud_items": { "time": 3 }
+            }"#,
         );
-        std::process::exit(1);
-    }
-    [
-        vec.get(0)
-            .unwrap()
-            .as_f64()
-            .expect("the value should be f64") as f32,
-        vec.get(1)
-            .unwrap()
-            .as_f64()
-            .expect("the value should be f64") as f32,
-        vec.get(2)
-            .unwrap()
-            .as_f64()
-            .expect("the value should be f64") as f32,
-        vec.get(3)
-            .unwrap()
-            .as_f64()
-            .expect("the value should be f64") as f32,
-    ]
+
+        assert_eq!(
+            paths,
+            [
+                "$.difficulty.curve",
+                "$.foods.bonus.points",
+                "$.hud_items.time"
+            ]
+        );
+    }
+
+    #[test]
+    fn nested_values_keep_the_defaults_of_their_siblings() {
+        let config =
+            Config::from_str(r#"{ "foods": { "bonus": { "points": 7 }, "bonuss": 1 } }"#).unwrap();
+
+        assert_eq!(config.foods.bonus.points, 7);
+        assert_eq!(config.foods.bonus.growth, Foods::new().bonus.growth);
+        assert_eq!(config.foods.normal, Foods::new().normal);
+    }
+
+    #[test]
+    fn screen_sizes_have_to_fit_the_cells() {
+        let paths = error_paths(r#"{ "screen_w": 510, "screen_h": 610 }"#);
+
+        assert_eq!(paths, ["$.screen_w", "$.screen_h"]);
+    }
+
+    #[test]
+    fn validate_reports_the_broken_values() {
+        let mut config = Config::new();
+        config.random_obstacle_count = 10_000;
+        config.difficulty.min_delay = 0.0;
+        config.foods.golden.points = MAX_POINTS + 1;
+        config.foods.expiring.min_points = 20;
+        config.power_ups.magnet.duration = 0;
+        config.background_color[2] = 1.5;
+
+        let paths: Vec<String> = config
+            .validate()
+            .into_iter()
+            .map(|error| error.path)
+            .collect();
+
+        assert_eq!(
+            paths,
+            [
+                "$.difficulty.min_delay",
+                "$.foods.golden.points",
+                "$.foods.expiring.max_points",
+                "$.power_ups.magnet.duration",
+                "$.random_obstacle_count",
+                "$.background_color[2]",
+            ]
+        );
+    }
+
+    #[test]
+    fn a_zero_cell_width_stops_the_checks() {
+        let mut config = Config::new();
+        config.cell_w = 0.0;
+
+        let errors = config.validate();
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].path, "$.cell_w");
+    }
+
+    #[test]
+    fn movement_delay_follows_the_curve() {
+        let mut difficulty = Difficulty::new();
+        difficulty.max_delay = 100.0;
+        difficulty.min_delay = 40.0;
+        difficulty.rate = 10.0;
+        difficulty.step_every = 3;
+
+        difficulty.curve = DifficultyCurve::Constant;
+        assert_eq!(difficulty.movement_delay(5), 100.0);
+
+        difficulty.curve = DifficultyCurve::Linear;
+        assert_eq!(difficulty.movement_delay(0), 100.0);
+        assert_eq!(difficulty.movement_delay(2), 80.0);
+        assert_eq!(difficulty.movement_delay(100), 40.0);
+
+        difficulty.curve = DifficultyCurve::Stepped;
+        assert_eq!(difficulty.movement_delay(2), 100.0);
+        assert_eq!(difficulty.movement_delay(3), 90.0);
+        assert_eq!(difficulty.movement_delay(7), 80.0);
+
+        difficulty.curve = DifficultyCurve::Exponential;
+        assert!((difficulty.movement_delay(2) - 81.0).abs() < 1e-9);
+        assert_eq!(difficulty.movement_delay(100), 40.0);
+    }
+
+    #[test]
+    fn expiring_food_is_worth_less_over_time() {
+        let expiring = ExpiringFood {
+            spawn_chance: 0.5,
+            lifetime: 40,
+            max_points: 10,
+            min_points: 2,
+        };
+
+        assert_eq!(expiring.points(40), 10);
+        assert_eq!(expiring.points(100), 10);
+        assert_eq!(expiring.points(20), 6);
+        assert_eq!(expiring.points(1), 2);
+        assert_eq!(expiring.points(0), 2);
+    }
 }
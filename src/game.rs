@@ -1,353 +1,1167 @@
-use crate::config::Config;
-use crate::snake::{Direction, Node, Snake};
+use crate::color::snake_node_color;
+use crate::config::{Config, ConfigError, DifficultyCurve, EdgeMode, HudPosition, PowerUpType};
+use crate::replay::Replay;
+use crate::scores::{ScoreBoard, ScoreEntry};
+use crate::screen::{MenuItem, Screen};
+use crate::settings::{Setting, SettingsEditor};
+use crate::simulation::Simulation;
+use crate::snake::Milliseconds;
+use crate::snake::Node;
+use crate::theme::{next_theme_name, Theme};
+use crate::watcher::ConfigWatcher;
 use piston::input::{Key, RenderArgs, UpdateArgs};
 use piston_window::*;
-use rand::thread_rng;
-use rand::Rng;
-use std::collections::LinkedList;
 
 const SEPERATOR_LINE_RADIUS: f64 = 0.5;
+/// How see-through the snake is while the ghost effect is active.
+const GHOST_ALPHA: f32 = 0.5;
+/// The half width of the border drawn along the wall and bounce edges.
+const WALL_LINE_RADIUS: f64 = 2.0;
+/// The color of the bars around the board when the window doesn't have
+/// the aspect ratio of the config.
+const LETTERBOX_COLOR: types::Color = [0.0, 0.0, 0.0, 1.0];
+const REPLAY_SPEEDS: [f64; 4] = [1.0, 2.0, 4.0, 8.0];
+/// The number of settings listed on the settings screen at once.
+const VISIBLE_SETTINGS: usize = 17;
+/// How long a toast stays on the screen.
+const TOAST_DURATION: Milliseconds = 4000.0;
 
 pub struct Game {
-    pub config: Config,
     pub window: PistonWindow,
-    pub snake: Snake,
-    pub food: Node,
-    pub obstacles: LinkedList<Node>,
-    pub score: u16,
-    pub high_score: u16,
-    pub direction_queue: Vec<Direction>,
+    pub simulation: Simulation,
+    pub replay_controls: Option<ReplayControls>,
+    pub scores: ScoreBoard,
+    pub screen: Screen,
+    /// The selected item of the menu on the current screen.
+    pub menu_index: usize,
+    pub settings: SettingsEditor,
+    /// Watches the file the settings are saved to.
+    pub config_watcher: ConfigWatcher,
+    pub toast: Option<Toast>,
+    /// The name of the theme picked last, to cycle on from.
+    pub theme_name: Option<String>,
 }
 
-impl Game {
-    pub fn render(&mut self, _args: &RenderArgs, event: &Event, glyphs: &mut Glyphs) {
-        let font_size = 32;
-        let text_padding = 10.0;
-
-        self.window.draw_2d(event, |c, g, device| {
-            // Check if the snake is dead
-            if !self.snake.is_alive {
-                render_game_over(c, g, glyphs, self.score, self.high_score, self.config);
-                glyphs.factory.encoder.flush(device);
-                return;
-            }
+/// A short message shown on top of every screen for a while.
+pub struct Toast {
+    pub text: String,
+    pub remaining: Milliseconds,
+}
 
-            // Clear the screen
-            clear(self.config.background_color, g);
-            let num_of_cells_horizontal = (self.config.screen_w / self.config.cell_w) as i32;
-            let num_of_cells_vertical = (self.config.screen_h / self.config.cell_w) as i32;
+/// The playback state while watching a replay.
+pub struct ReplayControls {
+    pub speed_index: usize,
+}
 
-            // Draw the food
-            rectangle(
-                self.config.food_color,
-                [
-                    self.config.cell_w * self.food.x,
-                    self.config.cell_w * self.food.y,
-                    self.config.cell_w,
-                    self.config.cell_w,
-                ],
-                c.transform,
-                g,
-            );
+impl ReplayControls {
+    pub fn new() -> Self {
+        ReplayControls { speed_index: 0 }
+    }
 
-            // Draw the snake
-            let mut node_index = 1.0;
-
-            let (snake_first_color, snake_second_color) = match self.snake.is_turbo {
-                true => (
-                    self.config.snake_turbo_first_color,
-                    self.config.snake_turbo_second_color,
-                ),
-                false => (
-                    self.config.snake_first_color,
-                    self.config.snake_second_color,
-                ),
-            };
+    pub fn speed(&self) -> f64 {
+        REPLAY_SPEEDS[self.speed_index]
+    }
+}
 
-            for node in self.snake.nodes.iter().rev() {
-                rectangle(
-                    [
-                        lerp(
-                            *snake_second_color.get(0).unwrap(),
-                            *snake_first_color.get(0).unwrap(),
-                            self.snake.nodes.len(),
-                            node_index,
-                        ),
-                        lerp(
-                            *snake_second_color.get(1).unwrap(),
-                            *snake_first_color.get(1).unwrap(),
-                            self.snake.nodes.len(),
-                            node_index,
-                        ),
-                        lerp(
-                            *snake_second_color.get(2).unwrap(),
-                            *snake_first_color.get(2).unwrap(),
-                            self.snake.nodes.len(),
-                            node_index,
-                        ),
-                        lerp(
-                            *snake_second_color.get(3).unwrap(),
-                            *snake_first_color.get(3).unwrap(),
-                            self.snake.nodes.len(),
-                            node_index,
-                        ),
-                    ],
-                    [
-                        self.config.cell_w * node.x,
-                        self.config.cell_w * node.y,
-                        self.config.cell_w,
-                        self.config.cell_w,
-                    ],
-                    c.transform,
-                    g,
-                );
+impl Game {
+    pub fn render(&mut self, args: &RenderArgs, event: &Event, glyphs: &mut Glyphs) {
+        let simulation = &self.simulation;
+        let config = &simulation.config;
 
-                node_index += 1.0;
-            }
+        // Scale the board uniformly to fit the window and center it
+        let scale =
+            (args.window_size[0] / config.screen_w).min(args.window_size[1] / config.canvas_h());
+        let offset_x = (args.window_size[0] - config.screen_w * scale) / 2.0;
+        let offset_y = (args.window_size[1] - config.canvas_h() * scale) / 2.0;
 
-            // Redraw the head in a different color
-            rectangle(
-                self.config.snake_head_color,
-                [
-                    self.config.cell_w * self.snake.nodes.front().unwrap().x,
-                    self.config.cell_w * self.snake.nodes.front().unwrap().y,
-                    self.config.cell_w,
-                    self.config.cell_w,
-                ],
-                c.transform,
-                g,
-            );
+        let replay_controls = &self.replay_controls;
+        let leaderboard = self.scores.leaderboard(config);
+        let screen = self.screen;
+        let menu_index = self.menu_index;
+        let settings = &self.settings;
+        let toast = &self.toast;
 
-            // Draw the obstacles
-            for obstacle in self.obstacles.iter() {
-                rectangle(
-                    self.config.obstacle_color,
-                    [
-                        self.config.cell_w * obstacle.x,
-                        self.config.cell_w * obstacle.y,
-                        self.config.cell_w,
-                        self.config.cell_w,
-                    ],
-                    c.transform,
-                    g,
-                );
+        self.window.draw_2d(event, |c, g, device| {
+            clear(LETTERBOX_COLOR, g);
+            let c = c.trans(offset_x, offset_y).scale(scale, scale);
+            let board_c = c.trans(0.0, config.board_y());
 
-                node_index += 1.0;
-            }
+            match screen {
+                Screen::Title => render_title(c, g, glyphs, config),
+                Screen::Menu => render_menu(c, g, glyphs, config, screen.menu_items(), menu_index),
+                Screen::Playing => render_game(c, g, glyphs, simulation, replay_controls),
+                Screen::Paused => {
+                    render_game(c, g, glyphs, simulation, replay_controls);
 
-            // Draw the seperator lines
-            for i in 1..num_of_cells_horizontal {
-                line_from_to(
-                    self.config.seperator_line_color,
-                    SEPERATOR_LINE_RADIUS,
-                    [self.config.cell_w * i as f64, 0.0],
-                    [self.config.cell_w * i as f64, self.config.screen_h],
-                    c.transform,
-                    g,
-                );
+                    // Keep the board visible while stepping through a replay
+                    if replay_controls.is_none() {
+                        render_pause(board_c, g, glyphs, config, screen.menu_items(), menu_index);
+                    }
+                }
+                Screen::GameOver => {
+                    render_game(c, g, glyphs, simulation, replay_controls);
+                    render_game_over(board_c, g, glyphs, simulation, leaderboard);
+                }
+                Screen::Settings => render_settings(c, g, glyphs, config, settings),
+                Screen::Leaderboard => render_leaderboard(c, g, glyphs, config, leaderboard),
             }
 
-            for i in 1..num_of_cells_vertical {
-                line_from_to(
-                    self.config.seperator_line_color,
-                    SEPERATOR_LINE_RADIUS,
-                    [0.0, self.config.cell_w * i as f64],
-                    [self.config.screen_w, self.config.cell_w * i as f64],
-                    c.transform,
-                    g,
-                );
+            if let Some(toast) = toast {
+                render_toast(c, g, glyphs, config, toast.text.as_str());
             }
 
-            text(
-                self.config.snake_first_color,
-                font_size,
-                self.score.to_string().as_str(),
-                glyphs,
-                c.transform
-                    .trans(text_padding, font_size as f64 + text_padding),
-                g,
-            )
-            .unwrap();
-
             glyphs.factory.encoder.flush(device);
         });
     }
 
     pub fn update(&mut self, args: &UpdateArgs) {
-        let dt_ms = args.dt * 1000.0;
-        self.snake.last_movement_duration += dt_ms;
+        let mut dt_ms = args.dt * 1000.0;
 
-        let mut delay = self.snake.movement_delay;
-        if self.snake.is_turbo {
-            delay /= 2.0;
+        if let Some(toast) = &mut self.toast {
+            toast.remaining -= dt_ms;
+            if toast.remaining <= 0.0 {
+                self.toast = None;
+            }
         }
 
-        if self.snake.last_movement_duration > delay {
-            self.snake.last_movement_duration = 0.0;
+        // Replays keep the config they were recorded with
+        if !self.simulation.is_replay() {
+            if let Some(result) = self.config_watcher.poll(dt_ms) {
+                self.reload_config(result);
+            }
+        }
 
-            if self.snake.is_alive {
-                // Check if there is a new direction in the input direction queue
-                if !self.direction_queue.is_empty() {
-                    let key = self.direction_queue.remove(0);
+        if self.screen != Screen::Playing {
+            return;
+        }
 
-                    match (key, &self.snake.direction) {
-                        (Direction::Up, Direction::Down) => {}
-                        (Direction::Down, Direction::Up) => {}
-                        (Direction::Right, Direction::Left) => {}
-                        (Direction::Left, Direction::Right) => {}
+        if let Some(replay_controls) = &self.replay_controls {
+            dt_ms *= replay_controls.speed();
+        }
 
-                        (Direction::Up, _) => self.snake.direction = Direction::Up,
-                        (Direction::Down, _) => self.snake.direction = Direction::Down,
-                        (Direction::Right, _) => self.snake.direction = Direction::Right,
-                        (Direction::Left, _) => self.snake.direction = Direction::Left,
-                    }
-                }
+        let was_alive = self.simulation.snake.is_alive;
+        self.simulation.update(dt_ms);
 
-                // Update the snakes location
-                match self.snake.direction {
-                    Direction::Up => self.snake.update_node_locations(0.0, -1.0, self.config),
-                    Direction::Down => self.snake.update_node_locations(0.0, 1.0, self.config),
-                    Direction::Right => self.snake.update_node_locations(1.0, 0.0, self.config),
-                    Direction::Left => self.snake.update_node_locations(-1.0, 0.0, self.config),
-                };
+        if was_alive && !self.simulation.snake.is_alive {
+            match self.simulation.has_won {
+                true => println!("You win! Seed: {}", self.simulation.seed),
+                false => println!("Game over! Seed: {}", self.simulation.seed),
+            }
 
-                // Check if the snake did bite itself
-                let mut snake_nodes_iter = self.snake.nodes.iter();
-                let head = snake_nodes_iter.next().unwrap().clone();
+            if !self.simulation.is_replay() {
+                self.record_score();
+            }
+        }
 
-                if snake_nodes_iter.any(|node| node.eq(head)) {
-                    self.game_over();
-                    return;
-                }
+        self.sync_screen();
+    }
 
-                // Check if the snake hit an obstacle
-                let mut obstacles_iter = self.obstacles.iter();
-                if obstacles_iter.any(|node| node.eq(head)) {
-                    self.game_over();
-                    return;
-                }
+    pub fn handle_key_press(&mut self, key: Key) {
+        let is_replay = self.simulation.is_replay();
 
-                // Check if the snake has eaten the food
-                if head.eq(self.food) {
-                    if self.snake.is_turbo {
-                        self.score += 2;
-                    } else {
-                        self.score += 1;
-                    }
-                    // Just push back a new random node
-                    // it will be updated automatically
-                    self.snake.nodes.push_back(Node { x: -1.0, y: -1.0 });
-                    self.place_random_food();
+        match self.screen {
+            Screen::Title => self.set_screen(Screen::Menu),
+            Screen::Menu => self.handle_menu_key(key),
+            Screen::Playing => {
+                if is_replay {
+                    self.handle_replay_key(key);
+                }
+                if key == Key::T {
+                    self.cycle_theme();
+                }
+                self.simulation.handle_key_press(key);
+            }
+            Screen::Paused => match key {
+                Key::P | Key::Escape => self.simulation.handle_key_press(key),
+                Key::Q => self.window.set_should_close(true),
+                Key::T => self.cycle_theme(),
+                _ if is_replay => self.handle_replay_key(key),
+                _ => self.handle_menu_key(key),
+            },
+            Screen::GameOver => match key {
+                Key::R if !is_replay => self.save_replay(),
+                Key::Escape if !is_replay => self.set_screen(Screen::Menu),
+                Key::Space => self.reset_game(),
+                _ => self.simulation.handle_key_press(key),
+            },
+            Screen::Settings => match key {
+                Key::Escape | Key::Backspace => self.set_screen(Screen::Menu),
+                Key::Return => self.save_settings(),
+                _ => self.settings.handle_key_press(key),
+            },
+            Screen::Leaderboard => {
+                if key == Key::Escape || key == Key::Backspace {
+                    self.set_screen(Screen::Menu);
                 }
             }
         }
+
+        self.sync_screen();
     }
 
-    pub fn handle_key_press(&mut self, key: Key) {
-        if !self.snake.is_alive {
-            if key == Key::Space {
-                self.reset_game();
+    pub fn handle_key_release(&mut self, key: Key) {
+        self.simulation.handle_key_release(key);
+    }
+
+    pub fn handle_focus(&mut self, is_focused: bool) {
+        // Don't let the snake run into something while the window is in the background
+        if !is_focused {
+            self.simulation.pause();
+            self.sync_screen();
+        }
+    }
+
+    fn set_screen(&mut self, screen: Screen) {
+        self.screen = screen;
+        self.menu_index = 0;
+    }
+
+    /// Follows the simulation while a game is on, as it decides whether
+    /// the game is running, paused or over.
+    fn sync_screen(&mut self) {
+        if let Screen::Playing | Screen::Paused | Screen::GameOver = self.screen {
+            let screen = if !self.simulation.snake.is_alive {
+                Screen::GameOver
+            } else if self.simulation.is_paused {
+                Screen::Paused
+            } else {
+                Screen::Playing
+            };
+
+            if screen != self.screen {
+                self.set_screen(screen);
             }
+        }
+    }
+
+    fn handle_menu_key(&mut self, key: Key) {
+        let items = self.screen.menu_items();
+        if items.is_empty() {
             return;
-        } else {
-            // Check for the turbo key
-            if key == Key::LShift {
-                self.snake.is_turbo = true;
+        }
+
+        match key {
+            Key::Up | Key::W => self.menu_index = (self.menu_index + items.len() - 1) % items.len(),
+            Key::Down | Key::S => self.menu_index = (self.menu_index + 1) % items.len(),
+            Key::Return | Key::Space => self.select_menu_item(items[self.menu_index]),
+            _ => {}
+        }
+    }
+
+    fn select_menu_item(&mut self, item: MenuItem) {
+        match item {
+            MenuItem::Play | MenuItem::Restart => {
+                self.reset_game();
+                self.set_screen(Screen::Playing);
             }
+            MenuItem::Resume => {
+                self.simulation.is_paused = false;
+                self.set_screen(Screen::Playing);
+            }
+            MenuItem::Leaderboard => self.set_screen(Screen::Leaderboard),
+            MenuItem::Settings => match self.config_watcher.load_file() {
+                Ok(config) => {
+                    self.settings = SettingsEditor::new(config);
+                    self.set_screen(Screen::Settings);
+                }
+                Err(e) => {
+                    eprintln!("{}", e);
+                    self.show_toast("Fix the config file to edit the settings");
+                }
+            },
+            MenuItem::MainMenu => self.set_screen(Screen::Menu),
+            MenuItem::Quit => self.window.set_should_close(true),
+        }
+    }
 
+    fn handle_replay_key(&mut self, key: Key) {
+        if let Some(replay_controls) = &mut self.replay_controls {
             match key {
-                Key::Up | Key::W => self.direction_queue.push(Direction::Up),
-                Key::Down | Key::S => self.direction_queue.push(Direction::Down),
-                Key::Right | Key::D => self.direction_queue.push(Direction::Right),
-                Key::Left | Key::A => self.direction_queue.push(Direction::Left),
+                Key::F => {
+                    replay_controls.speed_index =
+                        (replay_controls.speed_index + 1) % REPLAY_SPEEDS.len()
+                }
+                // Step a single tick while paused
+                Key::N if self.simulation.is_paused => self.simulation.step(),
                 _ => {}
             }
         }
     }
 
-    pub fn handle_key_release(&mut self, key: Key) {
-        if self.snake.is_alive {
-            if key == Key::LShift {
-                self.snake.is_turbo = false;
-            }
+    fn record_score(&mut self) {
+        let simulation = &mut self.simulation;
+        self.scores.record(
+            &simulation.config,
+            simulation.score,
+            simulation.seed,
+            simulation.has_won,
+        );
+
+        if let Err(e) = self.scores.save() {
+            eprintln!("Failed to save the scores: {}", e);
         }
+
+        simulation.high_score = self.scores.high_score(&simulation.config);
     }
 
-    pub fn place_random_obstacles(&mut self, count: u32) {
-        for _ in 0..count {
-            let (x, y) = self.find_random_available_node();
-            self.obstacles.push_back(Node { x, y });
+    /// Starts a new game. The config reloaded in the meantime applies now,
+    /// so the high score is looked up again for it.
+    fn reset_game(&mut self) {
+        self.simulation.reset_game();
+        self.simulation.high_score = self.scores.high_score(&self.simulation.config);
+    }
+
+    /// Validates the draft of the settings screen, writes it to the config
+    /// file and applies it.
+    fn save_settings(&mut self) {
+        let draft = self.settings.draft;
+
+        if let Some(error) = draft.validate().first() {
+            self.settings.status = Some(format!("{}: {}", error.path, error.message));
+            return;
+        }
+
+        if let Err(e) = draft.save_config_file(&self.config_watcher.path) {
+            self.settings.status = Some(format!("Failed to save: {}", e));
+            return;
         }
+
+        // Don't reload the file that was just written
+        self.config_watcher.mark_seen();
+
+        // The command line still applies on top of the saved file
+        let config = self.config_watcher.apply_overrides(draft).unwrap_or(draft);
+        self.simulation.apply_config(config);
+        self.reset_game();
+
+        self.settings.status = Some(String::from("Saved"));
     }
 
-    pub fn place_random_food(&mut self) {
-        let (rand_x, rand_y) = self.find_random_available_node();
-        self.food.x = rand_x;
-        self.food.y = rand_y;
+    /// Applies a config that was changed on disk: the colors right away
+    /// and the rest of it on the next game.
+    fn reload_config(&mut self, result: Result<Config, ConfigError>) {
+        match result {
+            Ok(config) => {
+                self.simulation.apply_config(config);
+                self.show_toast("Config reloaded");
+            }
+            Err(e) => {
+                eprintln!("{}", e);
+
+                // Keep playing with the current config and show the first problem
+                let text = match e.errors.as_slice() {
+                    [] => String::from("Invalid config"),
+                    [error] => format!("Invalid config: {}: {}", error.path, error.message),
+                    [error, rest @ ..] => format!(
+                        "Invalid config: {}: {} (and {} more)",
+                        error.path,
+                        error.message,
+                        rest.len()
+                    ),
+                };
+                self.show_toast(text);
+            }
+        }
     }
 
-    fn find_random_available_node(&self) -> (f64, f64) {
-        let x_len = self.config.screen_w / self.config.cell_w;
-        let y_len = self.config.screen_h / self.config.cell_w;
+    /// Switches to the colors of the next theme for the rest of the session.
+    fn cycle_theme(&mut self) {
+        let name = next_theme_name(self.theme_name.as_deref());
 
-        let mut rng = thread_rng();
-        loop {
-            let random_node = Node {
-                x: rng.gen_range(0, x_len as i32) as f64,
-                y: rng.gen_range(0, y_len as i32) as f64,
-            };
+        match Theme::load(name.as_str()) {
+            Ok(theme) => {
+                let mut config = self.simulation.config;
+                theme.apply(&mut config);
+                self.simulation.apply_colors(&config);
 
-            // Check if the snake is on those coordinates
-            let mut snake_nodes_iter = self.snake.nodes.iter();
-            if snake_nodes_iter.any(|node| node.eq(random_node)) {
-                continue;
-            }
+                if let Some(pending_config) = &mut self.simulation.pending_config {
+                    theme.apply(pending_config);
+                }
 
-            // Check if the food is on those coordinates
-            if self.food.eq(random_node) {
-                continue;
+                self.show_toast(format!("Theme: {}", theme.name));
+            }
+            Err(e) => {
+                eprintln!("{}", e);
+                self.show_toast(format!("Failed to load the theme {}", name));
             }
+        }
+
+        self.theme_name = Some(name);
+    }
+
+    fn show_toast<T: Into<String>>(&mut self, text: T) {
+        self.toast = Some(Toast {
+            text: text.into(),
+            remaining: TOAST_DURATION,
+        });
+    }
+
+    fn save_replay(&mut self) {
+        let replay_path = Replay::default_path();
+        match self.simulation.recording.save(&replay_path) {
+            Ok(()) => self.show_toast(format!("Replay saved to {}", replay_path.display())),
+            Err(e) => self.show_toast(format!("Failed to save the replay: {}", e)),
+        }
+    }
+}
+
+/// Renders the playfield along with the HUD bar.
+fn render_game(
+    c: Context,
+    g: &mut G2d,
+    glyphs: &mut Glyphs,
+    simulation: &Simulation,
+    replay_controls: &Option<ReplayControls>,
+) {
+    let config = &simulation.config;
+    fill_background(c, g, config);
+
+    render_board(c.trans(0.0, config.board_y()), g, simulation);
+
+    let hud_y = match config.hud_position {
+        HudPosition::Bottom => config.screen_h,
+        _ => 0.0,
+    };
+    render_hud(c.trans(0.0, hud_y), g, glyphs, simulation, replay_controls);
+}
+
+fn render_board(c: Context, g: &mut G2d, simulation: &Simulation) {
+    let config = &simulation.config;
+    let num_of_cells_horizontal = config.grid_w();
+    let num_of_cells_vertical = config.grid_h();
+    let board_w = config.cell_w * num_of_cells_horizontal as f64;
+    let board_h = config.cell_w * num_of_cells_vertical as f64;
+
+    // Draw the food
+    for food in simulation.foods.iter() {
+        let color = config.color_of_food(food.food_type);
+        let x = config.cell_w * food.cell.x as f64;
+        let y = config.cell_w * food.cell.y as f64;
+        rectangle(color, [x, y, config.cell_w, config.cell_w], c.transform, g);
+
+        // Close a ring around expiring food as its time runs out
+        if let Some(expires_at) = food.expires_at {
+            render_countdown_ring(
+                color,
+                food.cell,
+                expires_at,
+                config.foods.expiring.lifetime,
+                simulation,
+                c,
+                g,
+            );
+        }
+    }
+
+    // Draw the power-ups as circles, so they stand out from the food
+    for power_up in simulation.power_ups.iter() {
+        let color = config.color_of_power_up(power_up.power_up_type);
+        ellipse(
+            color,
+            [
+                config.cell_w * power_up.cell.x as f64,
+                config.cell_w * power_up.cell.y as f64,
+                config.cell_w,
+                config.cell_w,
+            ],
+            c.transform,
+            g,
+        );
+
+        render_countdown_ring(
+            color,
+            power_up.cell,
+            power_up.expires_at,
+            config.power_ups.lifetime,
+            simulation,
+            c,
+            g,
+        );
+    }
+
+    // Draw the snake
+    let nodes = &simulation.snake.nodes;
+    let head = *nodes.front().unwrap();
+    let tail = *nodes.back().unwrap();
+
+    // Slide the head and the tail from their last cells while waiting for
+    // the next move, the rest of the snake stays in its cells
+    let progress = match config.smooth_movement && simulation.snake.is_alive && simulation.tick > 0
+    {
+        true => (simulation.snake.last_movement_duration / simulation.movement_delay()).min(1.0),
+        false => 1.0,
+    };
+    let head_position = match nodes.get(1) {
+        Some(neck) => interpolate_cell(*neck, head, progress),
+        None => [head.x as f64, head.y as f64],
+    };
+
+    // A ghost snake is see-through
+    let body_color = |mut color: types::Color| {
+        if simulation.snake.has_effect(PowerUpType::Ghost) {
+            color[3] *= GHOST_ALPHA;
+        }
+        color
+    };
+
+    // Slide the end of the tail out of the cell it has left
+    if let Some(previous_tail) = simulation.previous_tail {
+        if progress < 1.0 {
+            render_cell(
+                body_color(snake_node_color(
+                    config,
+                    simulation.snake.is_turbo,
+                    nodes.len(),
+                    1.0,
+                )),
+                interpolate_cell(previous_tail, tail, progress),
+                config,
+                c,
+                g,
+            );
+        }
+    }
+
+    let mut node_index = 1.0;
+
+    for node in nodes.iter().rev() {
+        let position = match *node == head {
+            true => head_position,
+            false => [node.x as f64, node.y as f64],
+        };
+
+        render_cell(
+            body_color(snake_node_color(
+                config,
+                simulation.snake.is_turbo,
+                simulation.snake.nodes.len(),
+                node_index,
+            )),
+            position,
+            config,
+            c,
+            g,
+        );
+
+        node_index += 1.0;
+    }
+
+    // Redraw the head in a different color
+    render_cell(
+        body_color(config.snake_head_color),
+        head_position,
+        config,
+        c,
+        g,
+    );
+
+    // Show the shield around the head
+    if simulation.snake.has_effect(PowerUpType::Shield) {
+        let border_radius = config.cell_w / 10.0;
+        Rectangle::new_border(config.shield_color, border_radius).draw(
+            [
+                config.cell_w * head_position[0] + border_radius,
+                config.cell_w * head_position[1] + border_radius,
+                config.cell_w - 2.0 * border_radius,
+                config.cell_w - 2.0 * border_radius,
+            ],
+            &c.draw_state,
+            c.transform,
+            g,
+        );
+    }
+
+    // Draw the obstacles
+    for obstacle in simulation.obstacles.iter() {
+        rectangle(
+            config.obstacle_color,
+            [
+                config.cell_w * obstacle.x as f64,
+                config.cell_w * obstacle.y as f64,
+                config.cell_w,
+                config.cell_w,
+            ],
+            c.transform,
+            g,
+        );
+    }
 
-            // Check if one of the obstacles are on this coordinates
-            let mut obstacles_iter = self.obstacles.iter();
-            if obstacles_iter.any(|node| node.eq(random_node)) {
-                continue;
+    // Mark the cell the game ended on
+    if let Some(cell) = simulation.death_cause.and_then(|cause| cause.cell()) {
+        let border_radius = config.cell_w / 10.0;
+        Rectangle::new_border(config.fatal_cell_color, border_radius).draw(
+            [
+                config.cell_w * cell.x as f64 + border_radius,
+                config.cell_w * cell.y as f64 + border_radius,
+                config.cell_w - 2.0 * border_radius,
+                config.cell_w - 2.0 * border_radius,
+            ],
+            &c.draw_state,
+            c.transform,
+            g,
+        );
+    }
+
+    // Draw the seperator lines
+    for i in 1..num_of_cells_horizontal {
+        line_from_to(
+            config.seperator_line_color,
+            SEPERATOR_LINE_RADIUS,
+            [config.cell_w * i as f64, 0.0],
+            [config.cell_w * i as f64, board_h],
+            c.transform,
+            g,
+        );
+    }
+
+    for i in 1..num_of_cells_vertical {
+        line_from_to(
+            config.seperator_line_color,
+            SEPERATOR_LINE_RADIUS,
+            [0.0, config.cell_w * i as f64],
+            [board_w, config.cell_w * i as f64],
+            c.transform,
+            g,
+        );
+    }
+
+    // Draw a border along the edges the snake can't pass, inside the board
+    if config.edge_x != EdgeMode::Wrap {
+        for x in [WALL_LINE_RADIUS, board_w - WALL_LINE_RADIUS] {
+            line_from_to(
+                config.obstacle_color,
+                WALL_LINE_RADIUS,
+                [x, 0.0],
+                [x, board_h],
+                c.transform,
+                g,
+            );
+        }
+    }
+
+    if config.edge_y != EdgeMode::Wrap {
+        for y in [WALL_LINE_RADIUS, board_h - WALL_LINE_RADIUS] {
+            line_from_to(
+                config.obstacle_color,
+                WALL_LINE_RADIUS,
+                [0.0, y],
+                [board_w, y],
+                c.transform,
+                g,
+            );
+        }
+    }
+}
+
+/// Draws a ring around the cell that closes as the time until
+/// `expires_at` runs out.
+fn render_countdown_ring(
+    color: types::Color,
+    cell: Node,
+    expires_at: u64,
+    lifetime: u64,
+    simulation: &Simulation,
+    c: Context,
+    g: &mut G2d,
+) {
+    let config = &simulation.config;
+    let move_progress = match simulation.snake.is_alive {
+        true => (simulation.snake.last_movement_duration / simulation.movement_delay()).min(1.0),
+        false => 0.0,
+    };
+    let moves_left = expires_at.saturating_sub(simulation.tick) as f64 - move_progress;
+    let time_left = (moves_left / lifetime as f64).clamp(0.0, 1.0);
+
+    let ring_w = config.cell_w / 8.0;
+    let start = -std::f64::consts::FRAC_PI_2;
+    circle_arc(
+        color,
+        ring_w / 2.0,
+        start,
+        start + std::f64::consts::TAU * time_left,
+        [
+            config.cell_w * cell.x as f64 - ring_w,
+            config.cell_w * cell.y as f64 - ring_w,
+            config.cell_w + 2.0 * ring_w,
+            config.cell_w + 2.0 * ring_w,
+        ],
+        c.transform,
+        g,
+    );
+}
+
+/// Returns the position between two neighbouring cells, in cells, taking
+/// the short way across the edges when the snake has wrapped around.
+fn interpolate_cell(from: Node, to: Node, progress: f64) -> [f64; 2] {
+    let mut x_change = (to.x - from.x) as f64;
+    let mut y_change = (to.y - from.y) as f64;
+
+    if x_change.abs() > 1.0 {
+        x_change = -x_change.signum();
+    }
+    if y_change.abs() > 1.0 {
+        y_change = -y_change.signum();
+    }
+
+    [
+        from.x as f64 + x_change * progress,
+        from.y as f64 + y_change * progress,
+    ]
+}
+
+/// Draws a cell at a position given in cells, which doesn't have to be
+/// whole. The parts of the cell outside the board show up on the opposite
+/// edge, like the snake does.
+fn render_cell(color: types::Color, position: [f64; 2], config: &Config, c: Context, g: &mut G2d) {
+    let board_w = config.cell_w * config.grid_w() as f64;
+    let board_h = config.cell_w * config.grid_h() as f64;
+    let x = position[0] * config.cell_w;
+    let y = position[1] * config.cell_w;
+
+    for offset_x in [-board_w, 0.0, board_w] {
+        for offset_y in [-board_h, 0.0, board_h] {
+            let left = (x + offset_x).max(0.0);
+            let top = (y + offset_y).max(0.0);
+            let right = (x + offset_x + config.cell_w).min(board_w);
+            let bottom = (y + offset_y + config.cell_w).min(board_h);
+
+            if right > left && bottom > top {
+                rectangle(
+                    color,
+                    [left, top, right - left, bottom - top],
+                    c.transform,
+                    g,
+                );
             }
+        }
+    }
+}
+
+fn render_hud(
+    c: Context,
+    g: &mut G2d,
+    glyphs: &mut Glyphs,
+    simulation: &Simulation,
+    replay_controls: &Option<ReplayControls>,
+) {
+    let config = &simulation.config;
+    if config.hud_position == HudPosition::Hidden {
+        return;
+    }
+
+    rectangle(
+        config.hud_color,
+        [0.0, 0.0, config.screen_w, config.hud_h],
+        c.transform,
+        g,
+    );
+
+    // Separate the bar from the playfield
+    let line_y = match config.hud_position {
+        HudPosition::Top => config.hud_h,
+        _ => 0.0,
+    };
+    line_from_to(
+        config.seperator_line_color,
+        SEPERATOR_LINE_RADIUS,
+        [0.0, line_y],
+        [config.screen_w, line_y],
+        c.transform,
+        g,
+    );
+
+    let items = hud_items(simulation, replay_controls);
+    if items.is_empty() {
+        return;
+    }
+
+    // Give every item the same width and center it there
+    let font_size = ((config.hud_h * 0.4).round() as types::FontSize).max(1);
+    let item_w = config.screen_w / items.len() as f64;
+    let text_y = (config.hud_h + font_size as f64 * 0.7) / 2.0;
+
+    for (index, (item_text, color)) in items.iter().enumerate() {
+        let text_width = render_text_width(font_size, item_text.as_str(), glyphs, c);
+        let text_x = item_w * index as f64 + (item_w - text_width) / 2.0;
+
+        render_text(
+            *color,
+            font_size,
+            item_text.as_str(),
+            glyphs,
+            [text_x, text_y],
+            c,
+            g,
+        );
+    }
+}
 
-            return (random_node.x, random_node.y);
+/// Returns the texts of the HUD items enabled in the config.
+fn hud_items(
+    simulation: &Simulation,
+    replay_controls: &Option<ReplayControls>,
+) -> Vec<(String, types::Color)> {
+    let config = &simulation.config;
+    let hud_items = &config.hud_items;
+    let mut items = Vec::new();
+
+    if hud_items.score {
+        items.push((format!("Score {}", simulation.score), config.text_color));
+    }
+
+    if hud_items.high_score {
+        items.push((format!("Best {}", simulation.high_score), config.text_color));
+    }
+
+    if hud_items.length {
+        let length = simulation.snake.nodes.len();
+        items.push((format!("Len {}", length), config.text_color));
+    }
+
+    if hud_items.time {
+        let seconds = (simulation.elapsed / 1000.0) as u64;
+        items.push((
+            format!("{}:{:02}", seconds / 60, seconds % 60),
+            config.text_color,
+        ));
+    }
+
+    if hud_items.speed {
+        let cells_per_second = 1000.0 / simulation.movement_delay();
+        items.push((format!("{:.1}/s", cells_per_second), config.text_color));
+    }
+
+    // Dim the turbo item while it is off
+    if hud_items.turbo {
+        let color = match simulation.snake.is_turbo {
+            true => config.text_color,
+            false => config.text_secondary_color,
+        };
+        items.push((String::from("Turbo"), color));
+    }
+
+    // Count down the active effects in the color of their power-up
+    if hud_items.power_ups {
+        for effect in simulation.snake.effects.iter() {
+            items.push((
+                format!("{} {}", effect.power_up_type.label(), effect.moves_left),
+                config.color_of_power_up(effect.power_up_type),
+            ));
         }
     }
 
-    fn game_over(&mut self) {
-        if self.score > self.high_score {
-            self.high_score = self.score;
+    // The level never changes without a difficulty curve
+    if hud_items.level && config.difficulty.curve != DifficultyCurve::Constant {
+        items.push((format!("Level {}", simulation.level()), config.text_color));
+    }
+
+    // Show the playback state when watching a replay
+    if let Some(replay_controls) = replay_controls {
+        let replay_text = match simulation.is_paused {
+            true => String::from("Paused"),
+            false => format!("Replay {}x", replay_controls.speed()),
+        };
+        items.push((replay_text, config.text_color));
+    }
+
+    items
+}
+
+fn render_title(c: Context, g: &mut G2d, glyphs: &mut Glyphs, config: &Config) {
+    fill_background(c, g, config);
+
+    render_text_center(
+        config.text_color,
+        72,
+        "Snake",
+        glyphs,
+        config.canvas_h() / 2.0 - 20.0,
+        c,
+        g,
+        config,
+    );
+
+    render_text_center(
+        config.text_secondary_color,
+        24,
+        "Press any key to start",
+        glyphs,
+        config.canvas_h() / 2.0 + 50.0,
+        c,
+        g,
+        config,
+    );
+}
+
+fn render_menu(
+    c: Context,
+    g: &mut G2d,
+    glyphs: &mut Glyphs,
+    config: &Config,
+    items: &[MenuItem],
+    selected: usize,
+) {
+    fill_background(c, g, config);
+
+    render_text_center(config.text_color, 64, "Snake", glyphs, 160.0, c, g, config);
+
+    render_menu_items(c, g, glyphs, config, items, selected, 260.0);
+}
+
+/// Renders the items of a menu below each other, marking the selected one.
+fn render_menu_items(
+    c: Context,
+    g: &mut G2d,
+    glyphs: &mut Glyphs,
+    config: &Config,
+    items: &[MenuItem],
+    selected: usize,
+    y: f64,
+) {
+    let item_spacing = 50.0;
+
+    for (index, item) in items.iter().enumerate() {
+        let (color, item_text) = match index == selected {
+            true => (config.text_color, format!("> {} <", item.label())),
+            false => (config.text_secondary_color, String::from(item.label())),
+        };
+
+        render_text_center(
+            color,
+            32,
+            item_text.as_str(),
+            glyphs,
+            y + item_spacing * index as f64,
+            c,
+            g,
+            config,
+        );
+    }
+}
+
+fn render_settings(
+    c: Context,
+    g: &mut G2d,
+    glyphs: &mut Glyphs,
+    config: &Config,
+    settings: &SettingsEditor,
+) {
+    fill_background(c, g, config);
+
+    render_text_center(
+        config.text_color,
+        40,
+        "Settings",
+        glyphs,
+        60.0,
+        c,
+        g,
+        config,
+    );
+
+    // Scroll the list along with the selection
+    let first_index = settings
+        .selected
+        .saturating_sub(VISIBLE_SETTINGS / 2)
+        .min(settings.settings.len().saturating_sub(VISIBLE_SETTINGS));
+    let last_index = (first_index + VISIBLE_SETTINGS).min(settings.settings.len());
+
+    for index in first_index..last_index {
+        let y = 100.0 + 20.0 * (index - first_index) as f64;
+        let setting_text = settings.setting_text(index);
+
+        let (color, setting_text) = match index == settings.selected {
+            true => (config.text_color, format!("> {} <", setting_text)),
+            false => (config.text_secondary_color, setting_text),
+        };
+
+        render_text_center(color, 14, setting_text.as_str(), glyphs, y, c, g, config);
+
+        // Show a swatch next to the colors
+        if let Setting::Color(name) = settings.settings[index] {
+            if let Some((_, swatch_color)) = settings
+                .draft
+                .colors()
+                .iter()
+                .find(|(color_name, _)| *color_name == name)
+            {
+                let swatch = [10.0, y - 13.0, 14.0, 14.0];
+                rectangle(*swatch_color, swatch, c.transform, g);
+                Rectangle::new_border(config.seperator_line_color, SEPERATOR_LINE_RADIUS).draw(
+                    swatch,
+                    &c.draw_state,
+                    c.transform,
+                    g,
+                );
+            }
+        }
+    }
+
+    render_snake_preview(
+        c,
+        g,
+        &settings.draft,
+        config.screen_w,
+        120.0 + 20.0 * VISIBLE_SETTINGS as f64,
+    );
+
+    if let Some(status) = &settings.status {
+        render_text_center(
+            config.text_color,
+            16,
+            status.as_str(),
+            glyphs,
+            config.canvas_h() - 50.0,
+            c,
+            g,
+            config,
+        );
+    }
+
+    render_text_center(
+        config.text_color,
+        14,
+        "Arrows edit, Tab channel, Enter save, Esc back",
+        glyphs,
+        config.canvas_h() - 20.0,
+        c,
+        g,
+        config,
+    );
+}
+
+/// Draws a snake and a turbo snake in the colors of the given draft,
+/// so the gradients can be judged while editing them.
+fn render_snake_preview(c: Context, g: &mut G2d, draft: &Config, screen_w: f64, y: f64) {
+    let node_count = 12;
+    let cell_w = 16.0;
+    let padding = 8.0;
+    let preview_w = cell_w * (node_count + 2) as f64 + 2.0 * padding;
+    let x = (screen_w - preview_w) / 2.0;
+
+    rectangle(
+        draft.background_color,
+        [x, y, preview_w, 2.0 * cell_w + 3.0 * padding],
+        c.transform,
+        g,
+    );
+
+    for (row, is_turbo) in [false, true].iter().enumerate() {
+        let row_y = y + padding + (cell_w + padding) * row as f64;
+
+        for node_index in 1..=node_count {
+            let color = match node_index == node_count {
+                true => draft.snake_head_color,
+                false => snake_node_color(draft, *is_turbo, node_count, node_index as f32),
+            };
+
+            rectangle(
+                color,
+                [
+                    x + padding + cell_w * (node_index - 1) as f64,
+                    row_y,
+                    cell_w,
+                    cell_w,
+                ],
+                c.transform,
+                g,
+            );
         }
 
-        self.snake.is_alive = false;
+        // Show the food and an obstacle next to the snakes
+        let item_color = match is_turbo {
+            true => draft.obstacle_color,
+            false => draft.food_color,
+        };
+        rectangle(
+            item_color,
+            [
+                x + padding + cell_w * (node_count + 1) as f64,
+                row_y,
+                cell_w,
+                cell_w,
+            ],
+            c.transform,
+            g,
+        );
     }
+}
+
+fn render_leaderboard(
+    c: Context,
+    g: &mut G2d,
+    glyphs: &mut Glyphs,
+    config: &Config,
+    leaderboard: &[ScoreEntry],
+) {
+    fill_background(c, g, config);
 
-    pub fn reset_game(&mut self) {
-        // Create the snake
-        let nodes: LinkedList<Node> = LinkedList::from([
-            Node { x: 10.0, y: 10.0 },
-            Node { x: 11.0, y: 10.0 },
-            Node { x: 12.0, y: 10.0 },
-        ]);
+    render_text_center(
+        config.text_color,
+        48,
+        "Top Scores",
+        glyphs,
+        100.0,
+        c,
+        g,
+        config,
+    );
 
-        self.snake.nodes = nodes;
-        self.snake.direction = Direction::Left;
-        self.snake.is_alive = true;
-        self.snake.is_turbo = false;
-        self.snake.last_movement_duration = 0.0;
+    if leaderboard.is_empty() {
+        render_text_center(
+            config.text_secondary_color,
+            24,
+            "No scores yet",
+            glyphs,
+            180.0,
+            c,
+            g,
+            config,
+        );
+    }
 
-        self.score = 0;
-        self.obstacles = LinkedList::new();
+    for (index, entry) in leaderboard.iter().enumerate() {
+        let entry_text = match entry.has_won {
+            true => format!("{}. {} (won)", index + 1, entry.score),
+            false => format!("{}. {}", index + 1, entry.score),
+        };
 
-        self.place_random_obstacles(self.config.random_obstacle_count);
-        self.place_random_food();
+        render_text_center(
+            config.text_secondary_color,
+            24,
+            entry_text.as_str(),
+            glyphs,
+            160.0 + 34.0 * index as f64,
+            c,
+            g,
+            config,
+        );
     }
+
+    render_text_center(
+        config.text_color,
+        24,
+        "Press Escape to go back",
+        glyphs,
+        config.canvas_h() - 40.0,
+        c,
+        g,
+        config,
+    );
 }
 
+fn render_toast(c: Context, g: &mut G2d, glyphs: &mut Glyphs, config: &Config, text: &str) {
+    rectangle(
+        [0.0, 0.0, 0.0, 0.7],
+        [0.0, 0.0, config.screen_w, 30.0],
+        c.transform,
+        g,
+    );
+
+    render_text_center([1.0, 1.0, 1.0, 1.0], 14, text, glyphs, 20.0, c, g, config);
+}
+
+#[allow(clippy::too_many_arguments)]
 fn render_text_center(
     color: types::Color,
     font_size: types::FontSize,
@@ -358,31 +1172,127 @@ fn render_text_center(
     g: &mut G2d,
     config: &Config,
 ) {
-    let text_width = glyphs.width(font_size, text_content).unwrap();
+    let text_width = render_text_width(font_size, text_content, glyphs, c);
     let text_x = (config.screen_w - text_width) / 2.0;
 
+    render_text(color, font_size, text_content, glyphs, [text_x, y], c, g);
+}
+
+/// Draws text at the given position of the board. The glyphs are rendered
+/// at the size they end up on the screen, so they stay sharp when the
+/// board is scaled up or shown on a HiDPI screen.
+fn render_text(
+    color: types::Color,
+    font_size: types::FontSize,
+    text_content: &str,
+    glyphs: &mut Glyphs,
+    position: [f64; 2],
+    c: Context,
+    g: &mut G2d,
+) {
+    let pixel_scale = pixel_scale(c);
+    let scaled_font_size = (font_size as f64 * pixel_scale).round().max(1.0);
+
     text(
         color,
-        font_size,
+        scaled_font_size as types::FontSize,
         text_content,
         glyphs,
-        c.transform.trans(text_x, y),
+        c.transform
+            .trans(position[0], position[1])
+            .zoom(font_size as f64 / scaled_font_size),
         g,
     )
     .unwrap();
 }
 
+/// Returns the width of the text on the board, as drawn by `render_text`.
+fn render_text_width(
+    font_size: types::FontSize,
+    text_content: &str,
+    glyphs: &mut Glyphs,
+    c: Context,
+) -> f64 {
+    let pixel_scale = pixel_scale(c);
+    let scaled_font_size = (font_size as f64 * pixel_scale).round().max(1.0);
+    let scaled_width = glyphs
+        .width(scaled_font_size as types::FontSize, text_content)
+        .unwrap();
+
+    scaled_width * font_size as f64 / scaled_font_size
+}
+
+/// Returns how many pixels of the screen a unit of the board covers.
+fn pixel_scale(c: Context) -> f64 {
+    // The transform maps the window to the -1..1 range of the viewport
+    match c.viewport {
+        Some(viewport) => c.transform[0][0] * viewport.draw_size[0] as f64 / 2.0,
+        None => 1.0,
+    }
+}
+
+/// Fills the canvas with the background color, leaving the letterbox bars.
+fn fill_background(c: Context, g: &mut G2d, config: &Config) {
+    rectangle(
+        config.background_color,
+        [0.0, 0.0, config.screen_w, config.canvas_h()],
+        c.transform,
+        g,
+    );
+}
+
+fn render_pause(
+    c: Context,
+    g: &mut G2d,
+    glyphs: &mut Glyphs,
+    config: &Config,
+    items: &[MenuItem],
+    selected: usize,
+) {
+    let pop_up_offset = 50.0;
+
+    // Pop-up square
+    rectangle(
+        [0.0, 0.0, 0.0, 0.5],
+        [
+            pop_up_offset,
+            pop_up_offset,
+            config.screen_w - 2.0 * pop_up_offset,
+            config.screen_h - 2.0 * pop_up_offset,
+        ],
+        c.transform,
+        g,
+    );
+
+    render_text_center(config.text_color, 48, "Paused", glyphs, 150.0, c, g, config);
+
+    render_menu_items(c, g, glyphs, config, items, selected, 240.0);
+
+    render_text_center(
+        config.text_secondary_color,
+        20,
+        "Press P or Escape to resume",
+        glyphs,
+        config.screen_h - 90.0,
+        c,
+        g,
+        config,
+    );
+}
+
 fn render_game_over(
     c: Context,
     g: &mut G2d,
     glyphs: &mut Glyphs,
-    score: u16,
-    high_score: u16,
-    config: Config,
+    simulation: &Simulation,
+    leaderboard: &[ScoreEntry],
 ) {
+    let config = simulation.config;
     let font_size = 32;
     let pop_up_offset = 50.0;
     let game_over_font_size = 48;
+    let leaderboard_font_size = 16;
+    let leaderboard_line_height = 20.0;
 
     // Pop-up square
     rectangle(
@@ -398,65 +1308,140 @@ fn render_game_over(
     );
 
     // Game over text
+    let game_over_text = match simulation.has_won {
+        true => "You Win",
+        false => "Game Over",
+    };
+
     render_text_center(
-        config.snake_first_color,
+        config.text_color,
         game_over_font_size,
-        "Game Over",
+        game_over_text,
         glyphs,
-        120.0,
+        110.0,
         c,
         g,
         &config,
     );
 
+    // Render what ended the game
+    if let Some(death_cause) = simulation.death_cause {
+        render_text_center(
+            config.text_secondary_color,
+            20,
+            death_cause.to_string().as_str(),
+            glyphs,
+            140.0,
+            c,
+            g,
+            &config,
+        );
+    }
+
     // Render current score
-    let current_score_text = format!("Score: {}", score);
+    let current_score_text = format!("Score: {}", simulation.score);
 
     render_text_center(
-        config.food_color,
+        config.text_secondary_color,
         font_size,
         current_score_text.as_str(),
         glyphs,
-        200.0,
+        180.0,
         c,
         g,
         &config,
     );
 
-    // Render high score
-    let high_score_text = format!("High Score: {}", high_score);
+    // Render high score, along with the boards filled this session
+    let high_score_text = match simulation.wins {
+        0 => format!("High Score: {}", simulation.high_score),
+        wins => format!("High Score: {}  Wins: {}", simulation.high_score, wins),
+    };
 
     render_text_center(
-        config.food_color,
+        config.text_secondary_color,
         font_size,
         high_score_text.as_str(),
         glyphs,
-        280.0,
+        215.0,
         c,
         g,
         &config,
     );
 
+    // Render the local leaderboard
+    if !leaderboard.is_empty() {
+        render_text_center(
+            config.text_color,
+            20,
+            "Top Scores",
+            glyphs,
+            245.0,
+            c,
+            g,
+            &config,
+        );
+
+        for (index, entry) in leaderboard.iter().enumerate() {
+            let entry_text = match entry.has_won {
+                true => format!("{}. {} (won)", index + 1, entry.score),
+                false => format!("{}. {}", index + 1, entry.score),
+            };
+
+            render_text_center(
+                config.text_secondary_color,
+                leaderboard_font_size,
+                entry_text.as_str(),
+                glyphs,
+                270.0 + leaderboard_line_height * index as f64,
+                c,
+                g,
+                &config,
+            );
+        }
+    }
+
     // Render info
+    let info_text = match simulation.is_replay() {
+        true => "Press space to restart!",
+        false => "Space to restart, Escape for the menu",
+    };
+
     render_text_center(
-        config.snake_first_color,
+        config.text_color,
         24,
-        "Press space to restart!",
+        info_text,
         glyphs,
-        360.0,
+        485.0,
         c,
         g,
         &config,
     );
-}
 
-fn lerp(from: f32, to: f32, step_count: usize, current_step: f32) -> f32 {
-    let should_increment = to > from;
-    let current_addition = (f32::abs(from - to) / step_count as f32) * current_step as f32;
+    // Render the seed so the run can be reproduced
+    let seed_text = format!("Seed: {}", simulation.seed);
+
+    render_text_center(
+        config.text_secondary_color,
+        16,
+        seed_text.as_str(),
+        glyphs,
+        515.0,
+        c,
+        g,
+        &config,
+    );
 
-    if should_increment {
-        return from + current_addition;
-    } else {
-        return from - current_addition;
+    if !simulation.is_replay() {
+        render_text_center(
+            config.text_secondary_color,
+            16,
+            "Press R to save the replay",
+            glyphs,
+            540.0,
+            c,
+            g,
+            &config,
+        );
     }
 }
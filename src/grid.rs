@@ -0,0 +1,283 @@
+use crate::snake::Node;
+
+const WORD_BITS: usize = 64;
+
+/// A bitset with a single bit for every cell of the grid.
+#[derive(Clone, Debug)]
+struct CellSet {
+    words: Vec<u64>,
+}
+
+impl CellSet {
+    fn new(cell_count: usize) -> Self {
+        CellSet {
+            words: vec![0; cell_count.div_ceil(WORD_BITS)],
+        }
+    }
+
+    fn insert(&mut self, index: usize) {
+        self.words[index / WORD_BITS] |= 1 << (index % WORD_BITS);
+    }
+
+    fn remove(&mut self, index: usize) {
+        self.words[index / WORD_BITS] &= !(1 << (index % WORD_BITS));
+    }
+
+    fn contains(&self, index: usize) -> bool {
+        self.words[index / WORD_BITS] & (1 << (index % WORD_BITS)) != 0
+    }
+}
+
+/// Keeps track of which cells are taken by the snake, the obstacles, the
+/// food and the power-ups, so lookups and free cell searches don't need to
+/// walk them.
+#[derive(Clone, Debug)]
+pub struct OccupancyGrid {
+    pub width: i32,
+    pub height: i32,
+    snake: CellSet,
+    /// The number of snake segments on every cell, which is more than one
+    /// where the snake passed through itself as a ghost.
+    snake_counts: Vec<u16>,
+    obstacles: CellSet,
+    food: CellSet,
+    power_ups: CellSet,
+}
+
+impl OccupancyGrid {
+    pub fn new(width: i32, height: i32) -> Self {
+        let cell_count = (width.max(0) * height.max(0)) as usize;
+
+        OccupancyGrid {
+            width,
+            height,
+            snake: CellSet::new(cell_count),
+            snake_counts: vec![0; cell_count],
+            obstacles: CellSet::new(cell_count),
+            food: CellSet::new(cell_count),
+            power_ups: CellSet::new(cell_count),
+        }
+    }
+
+    pub fn is_snake(&self, node: Node) -> bool {
+        self.index_of(node)
+            .is_some_and(|index| self.snake.contains(index))
+    }
+
+    pub fn is_obstacle(&self, node: Node) -> bool {
+        self.index_of(node)
+            .is_some_and(|index| self.obstacles.contains(index))
+    }
+
+    pub fn is_food(&self, node: Node) -> bool {
+        self.index_of(node)
+            .is_some_and(|index| self.food.contains(index))
+    }
+
+    pub fn is_power_up(&self, node: Node) -> bool {
+        self.index_of(node)
+            .is_some_and(|index| self.power_ups.contains(index))
+    }
+
+    /// Returns whether the cell is on the grid and not taken by anything.
+    pub fn is_free(&self, node: Node) -> bool {
+        match self.index_of(node) {
+            Some(index) => self.free_bits(index / WORD_BITS) & (1 << (index % WORD_BITS)) != 0,
+            None => false,
+        }
+    }
+
+    /// Puts a segment of the snake on the cell.
+    pub fn add_snake(&mut self, node: Node) {
+        if let Some(index) = self.index_of(node) {
+            self.snake_counts[index] += 1;
+            self.snake.insert(index);
+        }
+    }
+
+    /// Takes a segment of the snake off the cell, which stays taken while
+    /// other segments are still on it.
+    pub fn remove_snake(&mut self, node: Node) {
+        if let Some(index) = self.index_of(node) {
+            let count = &mut self.snake_counts[index];
+            *count = count.saturating_sub(1);
+            if *count == 0 {
+                self.snake.remove(index);
+            }
+        }
+    }
+
+    pub fn set_obstacle(&mut self, node: Node, is_set: bool) {
+        if let Some(index) = self.index_of(node) {
+            set_bit(&mut self.obstacles, index, is_set);
+        }
+    }
+
+    pub fn set_food(&mut self, node: Node, is_set: bool) {
+        if let Some(index) = self.index_of(node) {
+            set_bit(&mut self.food, index, is_set);
+        }
+    }
+
+    pub fn set_power_up(&mut self, node: Node, is_set: bool) {
+        if let Some(index) = self.index_of(node) {
+            set_bit(&mut self.power_ups, index, is_set);
+        }
+    }
+
+    /// Returns the number of cells that are not taken by anything.
+    pub fn free_count(&self) -> usize {
+        (0..self.snake.words.len())
+            .map(|word_index| self.free_bits(word_index).count_ones() as usize)
+            .sum()
+    }
+
+    /// Returns the `n`th free cell in row-major order.
+    pub fn nth_free(&self, mut n: usize) -> Option<Node> {
+        for word_index in 0..self.snake.words.len() {
+            let mut free = self.free_bits(word_index);
+            let free_count = free.count_ones() as usize;
+
+            if n < free_count {
+                // Drop the lowest free bits until the one we look for is the lowest
+                for _ in 0..n {
+                    free &= free - 1;
+                }
+                let index = word_index * WORD_BITS + free.trailing_zeros() as usize;
+                return Some(self.node_at(index));
+            }
+
+            n -= free_count;
+        }
+
+        None
+    }
+
+    fn free_bits(&self, word_index: usize) -> u64 {
+        let taken = self.snake.words[word_index]
+            | self.obstacles.words[word_index]
+            | self.food.words[word_index]
+            | self.power_ups.words[word_index];
+        let mut free = !taken;
+
+        // Ignore the bits past the last cell
+        let cell_count = (self.width * self.height) as usize;
+        let cells_in_word = cell_count - word_index * WORD_BITS;
+        if cells_in_word < WORD_BITS {
+            free &= (1 << cells_in_word) - 1;
+        }
+
+        free
+    }
+
+    fn index_of(&self, node: Node) -> Option<usize> {
+        if node.x < 0 || node.y < 0 || node.x >= self.width || node.y >= self.height {
+            return None;
+        }
+
+        Some((node.y * self.width + node.x) as usize)
+    }
+
+    fn node_at(&self, index: usize) -> Node {
+        Node {
+            x: index as i32 % self.width,
+            y: index as i32 / self.width,
+        }
+    }
+}
+
+fn set_bit(set: &mut CellSet, index: usize, is_set: bool) {
+    if is_set {
+        set.insert(index);
+    } else {
+        set.remove(index);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_cell_is_free_on_a_new_grid() {
+        // 100 cells leave 36 bits of the second word in use
+        let grid = OccupancyGrid::new(10, 10);
+
+        assert_eq!(grid.free_count(), 100);
+        assert_eq!(grid.nth_free(0), Some(Node { x: 0, y: 0 }));
+        assert_eq!(grid.nth_free(99), Some(Node { x: 9, y: 9 }));
+        assert_eq!(grid.nth_free(100), None);
+    }
+
+    #[test]
+    fn bits_past_the_last_cell_are_not_free() {
+        let mut grid = OccupancyGrid::new(7, 3);
+        assert_eq!(grid.free_count(), 21);
+
+        for x in 0..7 {
+            for y in 0..3 {
+                grid.set_obstacle(Node { x, y }, true);
+            }
+        }
+
+        assert_eq!(grid.free_count(), 0);
+        assert_eq!(grid.nth_free(0), None);
+    }
+
+    #[test]
+    fn a_full_last_word_is_kept_whole() {
+        let grid = OccupancyGrid::new(8, 8);
+
+        assert_eq!(grid.free_count(), 64);
+        assert_eq!(grid.nth_free(63), Some(Node { x: 7, y: 7 }));
+    }
+
+    #[test]
+    fn nth_free_skips_the_taken_cells() {
+        let mut grid = OccupancyGrid::new(10, 10);
+        grid.add_snake(Node { x: 0, y: 0 });
+        grid.set_obstacle(Node { x: 1, y: 0 }, true);
+        grid.set_food(Node { x: 3, y: 0 }, true);
+        grid.set_power_up(Node { x: 5, y: 9 }, true);
+
+        assert_eq!(grid.free_count(), 96);
+        assert_eq!(grid.nth_free(0), Some(Node { x: 2, y: 0 }));
+        assert_eq!(grid.nth_free(1), Some(Node { x: 4, y: 0 }));
+        // The power-up in the second word shifts the cells after it by one
+        assert_eq!(grid.nth_free(95), Some(Node { x: 9, y: 9 }));
+        assert_eq!(grid.nth_free(91), Some(Node { x: 4, y: 9 }));
+        assert_eq!(grid.nth_free(92), Some(Node { x: 6, y: 9 }));
+        assert!(!grid.is_free(Node { x: 5, y: 9 }));
+        assert!(grid.is_free(Node { x: 6, y: 9 }));
+    }
+
+    #[test]
+    fn cells_off_the_grid_are_neither_free_nor_taken() {
+        let grid = OccupancyGrid::new(4, 4);
+
+        for node in [
+            Node { x: -1, y: 0 },
+            Node { x: 0, y: -1 },
+            Node { x: 4, y: 0 },
+            Node { x: 0, y: 4 },
+        ] {
+            assert!(!grid.is_free(node));
+            assert!(!grid.is_snake(node));
+        }
+    }
+
+    #[test]
+    fn snake_cells_stay_taken_until_every_segment_left() {
+        let mut grid = OccupancyGrid::new(4, 4);
+        let cell = Node { x: 2, y: 1 };
+
+        grid.add_snake(cell);
+        grid.add_snake(cell);
+        grid.remove_snake(cell);
+        assert!(grid.is_snake(cell));
+
+        grid.remove_snake(cell);
+        assert!(!grid.is_snake(cell));
+        assert!(grid.is_free(cell));
+    }
+}
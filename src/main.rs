@@ -1,22 +1,111 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
+mod cli;
+mod color;
 mod config;
 mod game;
+mod grid;
+mod replay;
+mod scores;
+mod screen;
+mod settings;
+mod simulation;
 mod snake;
+mod terminal;
+mod theme;
+mod watcher;
 
-use game::Game;
+use cli::{Cli, Mode};
+use game::{Game, ReplayControls};
 use piston::event_loop::{EventSettings, Events};
-use piston::input::{Button, PressEvent, ReleaseEvent, RenderEvent, UpdateEvent};
+use piston::input::{Button, FocusEvent, PressEvent, ReleaseEvent, RenderEvent, UpdateEvent};
 use piston_window::{Glyphs, PistonWindow, TextureSettings, WindowSettings};
-use snake::{Direction, Node, Snake};
-use std::collections::LinkedList;
+use replay::Replay;
+use scores::ScoreBoard;
+use screen::Screen;
+use settings::SettingsEditor;
+use simulation::Simulation;
+use std::io::Write;
+use watcher::ConfigWatcher;
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let config = config::Config::load_config_file()?;
+    let cli = match Cli::parse(std::env::args().skip(1)) {
+        Ok(cli) => cli,
+        Err(e) => {
+            eprintln!("{}\nRun with --help to see the available options.", e);
+            std::process::exit(2);
+        }
+    };
+
+    if cli.should_print_help {
+        print!("{}", cli::help());
+        return Ok(());
+    }
+
+    let config = config::Config::load_config_file(cli.config_path.clone())
+        .and_then(|config| cli.apply_overrides(config));
+    let mut config = match config {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let replay = match &cli.replay_path {
+        Some(replay_path) => match Replay::load(replay_path) {
+            Ok(replay) => Some(replay),
+            Err(e) => {
+                eprintln!("Failed to load the replay {}: {}", replay_path.display(), e);
+                std::process::exit(1);
+            }
+        },
+        None => None,
+    };
+
+    if let Some(replay) = &replay {
+        config = replay.config;
+    }
+
+    if cli.should_print_config {
+        println!("{}", serde_json::to_string_pretty(&config)?);
+        return Ok(());
+    }
+
+    let simulation = match replay {
+        Some(replay) => Simulation::from_replay(replay),
+        None => Simulation::new(config),
+    };
+
+    match cli.mode {
+        Mode::Window => {
+            // Save the settings to the loaded file, or create one in the config dir
+            let config_path = config::Config::find_config_file(cli.config_path.clone())
+                .or_else(|| config::config_dir().map(|config_dir| config_dir.join("config.json")))
+                .ok_or("failed to find a path for the config file")?;
+            let theme_name = cli.theme.clone();
+            run_window(
+                simulation,
+                ConfigWatcher::new(config_path, cli.clone()),
+                theme_name,
+            )
+        }
+        Mode::Terminal => terminal::run_terminal(simulation),
+        Mode::Headless => run_headless(simulation, cli.ticks),
+    }
+}
 
-    let mut window: PistonWindow = WindowSettings::new("Snake", [config.screen_w, config.screen_h])
-        .exit_on_esc(true)
-        .resizable(false)
-        .build()?;
+fn run_window(
+    mut simulation: Simulation,
+    config_watcher: ConfigWatcher,
+    theme_name: Option<String>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let config = simulation.config;
+    let mut window: PistonWindow =
+        WindowSettings::new("Snake", [config.screen_w, config.canvas_h()])
+            .exit_on_esc(false)
+            .resizable(true)
+            .fullscreen(config.fullscreen)
+            .build()?;
 
     let font = include_bytes!("../assets/PlaymegamesReguler-2OOee.ttf");
     let mut glyphs = Glyphs::from_bytes(
@@ -26,30 +115,29 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     )
     .expect("failed to load glyphs from the provided font");
 
-    // Create the snake
-    let snake = Snake {
-        nodes: LinkedList::new(),
-        direction: Direction::Left,
-        is_alive: true,
-        is_turbo: false,
-        movement_delay: 80.0,
-        last_movement_duration: 0.0,
+    // Create a new game and run it.
+    // Replays skip the title screen and start playing right away
+    let (replay_controls, screen) = match simulation.is_replay() {
+        true => (Some(ReplayControls::new()), Screen::Playing),
+        false => (None, Screen::Title),
     };
 
-    // Create a new game and run it.
+    let scores = ScoreBoard::load();
+    simulation.high_score = scores.high_score(&simulation.config);
+
     let mut game = Game {
-        config,
         window,
-        snake,
-        food: Node { x: -1.0, y: -1.0 },
-        obstacles: LinkedList::new(),
-        score: 0,
-        high_score: 0,
-        direction_queue: vec![Direction::Up; 0],
+        simulation,
+        replay_controls,
+        scores,
+        screen,
+        menu_index: 0,
+        settings: SettingsEditor::new(config),
+        config_watcher,
+        toast: None,
+        theme_name,
     };
 
-    game.reset_game();
-
     let event_settings = EventSettings::new();
     let mut events = Events::new(event_settings);
     while let Some(e) = events.next(&mut game.window) {
@@ -68,6 +156,44 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         if let Some(Button::Keyboard(key)) = e.release_args() {
             game.handle_key_release(key)
         };
+
+        if let Some(is_focused) = e.focus_args() {
+            game.handle_focus(is_focused)
+        };
+    }
+
+    Ok(())
+}
+
+/// Runs the game without a window until the snake dies or `ticks` ticks
+/// have passed, and prints the result.
+fn run_headless(mut simulation: Simulation, ticks: u64) -> Result<(), Box<dyn std::error::Error>> {
+    while simulation.snake.is_alive && simulation.tick < ticks {
+        simulation.step();
+    }
+
+    // Show the final board, e.g. for a visual check in CI logs
+    let mut stdout = std::io::stdout();
+    terminal::draw_frame(
+        &mut stdout,
+        &simulation,
+        terminal::ColorMode::detect(),
+        false,
+    )?;
+    stdout.flush()?;
+
+    println!(
+        "Score: {}, Length: {}, Ticks: {}, Seed: {}, Alive: {}, Won: {}",
+        simulation.score,
+        simulation.snake.nodes.len(),
+        simulation.tick,
+        simulation.seed,
+        simulation.snake.is_alive,
+        simulation.has_won
+    );
+
+    if let Some(death_cause) = simulation.death_cause {
+        println!("Death: {}", death_cause);
     }
 
     Ok(())
@@ -0,0 +1,139 @@
+use crate::config::{data_dir, Config};
+use crate::snake::Direction;
+use std::fs::{create_dir_all, read_to_string, write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const REPLAY_VERSION: u64 = 2;
+
+/// A single input that fed the simulation.
+#[derive(Clone, Debug, Copy, PartialEq)]
+pub enum Input {
+    Direction(Direction),
+    Turbo(bool),
+}
+
+/// An input along with the tick it has to be applied before.
+#[derive(Clone, Debug, Copy)]
+pub struct InputEvent {
+    pub tick: u64,
+    pub input: Input,
+}
+
+/// Everything needed to re-run a game: the seed, the config
+/// and the stream of inputs.
+#[derive(Clone, Debug)]
+pub struct Replay {
+    pub seed: u64,
+    pub config: Config,
+    pub inputs: Vec<InputEvent>,
+}
+
+impl Replay {
+    pub fn new(seed: u64, config: Config) -> Self {
+        Replay {
+            seed,
+            config,
+            inputs: Vec::new(),
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Replay, Box<dyn std::error::Error>> {
+        let file_content = read_to_string(path)?;
+        let json: serde_json::Value = serde_json::from_str(file_content.as_str())?;
+
+        let version = json.get("version").and_then(|value| value.as_u64());
+        if version != Some(REPLAY_VERSION) {
+            return Err(format!("unsupported replay version {:?}", version).into());
+        }
+
+        let seed = json
+            .get("seed")
+            .and_then(|value| value.as_u64())
+            .ok_or("replay is missing the seed")?;
+
+        let mut config =
+            Config::from_json(json.get("config").ok_or("replay is missing the config")?)?;
+        config.seed = Some(seed);
+
+        let mut inputs = Vec::new();
+        if let Some(serde_json::Value::Array(events)) = json.get("inputs") {
+            for event in events {
+                let tick = event.get(0).and_then(|value| value.as_u64());
+                let input = event
+                    .get(1)
+                    .and_then(|value| value.as_str())
+                    .and_then(input_from_str);
+
+                match (tick, input) {
+                    (Some(tick), Some(input)) => inputs.push(InputEvent { tick, input }),
+                    _ => return Err(format!("invalid replay input {}", event).into()),
+                }
+            }
+        }
+
+        Ok(Replay {
+            seed,
+            config,
+            inputs,
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        let inputs: Vec<serde_json::Value> = self
+            .inputs
+            .iter()
+            .map(|event| serde_json::json!([event.tick, input_to_str(event.input)]))
+            .collect();
+
+        let json = serde_json::json!({
+            "version": REPLAY_VERSION,
+            "seed": self.seed,
+            "config": self.config.to_json(),
+            "inputs": inputs,
+        });
+
+        if let Some(parent) = path.parent() {
+            create_dir_all(parent)?;
+        }
+        write(path, serde_json::to_string(&json)?)?;
+        Ok(())
+    }
+
+    /// Returns a fresh file path in the data dir to save a replay to.
+    pub fn default_path() -> PathBuf {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or(0);
+
+        let file_name = format!("snake_replay_{}.json", timestamp);
+        match data_dir() {
+            Some(data_dir) => data_dir.join("replays").join(file_name),
+            None => PathBuf::from(file_name),
+        }
+    }
+}
+
+fn input_to_str(input: Input) -> &'static str {
+    match input {
+        Input::Direction(Direction::Up) => "up",
+        Input::Direction(Direction::Down) => "down",
+        Input::Direction(Direction::Right) => "right",
+        Input::Direction(Direction::Left) => "left",
+        Input::Turbo(true) => "turbo_on",
+        Input::Turbo(false) => "turbo_off",
+    }
+}
+
+fn input_from_str(input: &str) -> Option<Input> {
+    match input {
+        "up" => Some(Input::Direction(Direction::Up)),
+        "down" => Some(Input::Direction(Direction::Down)),
+        "right" => Some(Input::Direction(Direction::Right)),
+        "left" => Some(Input::Direction(Direction::Left)),
+        "turbo_on" => Some(Input::Turbo(true)),
+        "turbo_off" => Some(Input::Turbo(false)),
+        _ => None,
+    }
+}
@@ -0,0 +1,217 @@
+use crate::config::{data_dir, Config, Difficulty, EdgeMode, Foods, PowerUps};
+use std::collections::HashMap;
+use std::fs::{create_dir_all, read_to_string, write};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const SCORES_VERSION: u64 = 1;
+const LEADERBOARD_SIZE: usize = 10;
+const HISTORY_SIZE: usize = 100;
+
+#[derive(Clone, Debug, Copy)]
+pub struct ScoreEntry {
+    pub score: u16,
+    pub seed: u64,
+    pub timestamp: u64,
+    pub has_won: bool,
+}
+
+/// The scores of a single config.
+#[derive(Clone, Debug, Default)]
+struct Board {
+    leaderboard: Vec<ScoreEntry>,
+    history: Vec<ScoreEntry>,
+}
+
+/// The scores persisted on disk, kept separately for every grid setup
+/// so scores of different configs aren't mixed.
+pub struct ScoreBoard {
+    path: PathBuf,
+    boards: HashMap<String, Board>,
+}
+
+impl ScoreBoard {
+    /// Loads the scores file, falling back to empty scores if it is missing
+    /// or can't be read.
+    pub fn load() -> Self {
+        let path = scores_path();
+        let boards = match read_to_string(&path) {
+            Ok(file_content) => parse_boards(file_content.as_str()).unwrap_or_else(|| {
+                eprintln!("Ignoring the unreadable scores file at {}", path.display());
+                HashMap::new()
+            }),
+            Err(_) => HashMap::new(),
+        };
+
+        ScoreBoard { path, boards }
+    }
+
+    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let boards: serde_json::Map<String, serde_json::Value> = self
+            .boards
+            .iter()
+            .map(|(key, board)| {
+                let board_json = serde_json::json!({
+                    "leaderboard": entries_to_json(&board.leaderboard),
+                    "history": entries_to_json(&board.history),
+                });
+                (key.clone(), board_json)
+            })
+            .collect();
+
+        let json = serde_json::json!({
+            "version": SCORES_VERSION,
+            "boards": boards,
+        });
+
+        if let Some(parent) = self.path.parent() {
+            create_dir_all(parent)?;
+        }
+        write(&self.path, serde_json::to_string_pretty(&json)?)?;
+        Ok(())
+    }
+
+    /// Adds the result of a finished game to the scores of its config.
+    pub fn record(&mut self, config: &Config, score: u16, seed: u64, has_won: bool) {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or(0);
+        let entry = ScoreEntry {
+            score,
+            seed,
+            timestamp,
+            has_won,
+        };
+
+        let board = self.boards.entry(config_key(config)).or_default();
+
+        board.history.push(entry);
+        if board.history.len() > HISTORY_SIZE {
+            board.history.remove(0);
+        }
+
+        board.leaderboard.push(entry);
+        board
+            .leaderboard
+            .sort_by(|a, b| b.score.cmp(&a.score).then(a.timestamp.cmp(&b.timestamp)));
+        board.leaderboard.truncate(LEADERBOARD_SIZE);
+    }
+
+    /// Returns the best scores of the given config, the highest first.
+    pub fn leaderboard(&self, config: &Config) -> &[ScoreEntry] {
+        match self.boards.get(&config_key(config)) {
+            Some(board) => &board.leaderboard,
+            None => &[],
+        }
+    }
+
+    pub fn high_score(&self, config: &Config) -> u16 {
+        self.leaderboard(config)
+            .first()
+            .map_or(0, |entry| entry.score)
+    }
+}
+
+/// Returns a stable hash of the config fields that change the gameplay.
+fn config_key(config: &Config) -> String {
+    let mut fields = format!(
+        "{}|{}|{}|{}",
+        config.screen_w, config.screen_h, config.cell_w, config.random_obstacle_count
+    );
+
+    // Only a changed difficulty is part of the key, so the existing boards stay
+    if config.difficulty != Difficulty::new() {
+        if let Ok(difficulty) = serde_json::to_string(&config.difficulty) {
+            fields.push('|');
+            fields.push_str(difficulty.as_str());
+        }
+    }
+
+    // The same goes for edges that don't wrap
+    if config.edge_x != EdgeMode::Wrap || config.edge_y != EdgeMode::Wrap {
+        fields.push_str(format!("|{:?}|{:?}", config.edge_x, config.edge_y).as_str());
+    }
+
+    if config.foods != Foods::new() {
+        if let Ok(foods) = serde_json::to_string(&config.foods) {
+            fields.push('|');
+            fields.push_str(foods.as_str());
+        }
+    }
+
+    if config.power_ups != PowerUps::new() {
+        if let Ok(power_ups) = serde_json::to_string(&config.power_ups) {
+            fields.push('|');
+            fields.push_str(power_ups.as_str());
+        }
+    }
+
+    if let Some(food_timeout) = config.food_timeout {
+        fields.push_str(format!("|{}", food_timeout).as_str());
+    }
+
+    // FNV-1a, as the std hashers aren't guaranteed to be stable across releases
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in fields.bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+
+    format!("{:016x}", hash)
+}
+
+/// Returns the scores file in the data dir, or in the working directory
+/// when there is no home to put it in.
+fn scores_path() -> PathBuf {
+    match data_dir() {
+        Some(data_dir) => data_dir.join("scores.json"),
+        None => PathBuf::from("snake_scores.json"),
+    }
+}
+
+fn parse_boards(file_content: &str) -> Option<HashMap<String, Board>> {
+    let json: serde_json::Value = serde_json::from_str(file_content).ok()?;
+    if json.get("version")?.as_u64()? != SCORES_VERSION {
+        return None;
+    }
+
+    let mut boards = HashMap::new();
+    for (key, board) in json.get("boards")?.as_object()? {
+        let board = Board {
+            leaderboard: entries_from_json(board.get("leaderboard")?)?,
+            history: entries_from_json(board.get("history")?)?,
+        };
+        boards.insert(key.clone(), board);
+    }
+
+    Some(boards)
+}
+
+fn entries_to_json(entries: &[ScoreEntry]) -> serde_json::Value {
+    entries
+        .iter()
+        .map(|entry| {
+            serde_json::json!({
+                "score": entry.score,
+                "seed": entry.seed,
+                "timestamp": entry.timestamp,
+                "won": entry.has_won,
+            })
+        })
+        .collect()
+}
+
+fn entries_from_json(json: &serde_json::Value) -> Option<Vec<ScoreEntry>> {
+    json.as_array()?
+        .iter()
+        .map(|entry| {
+            Some(ScoreEntry {
+                score: entry.get("score")?.as_u64()? as u16,
+                seed: entry.get("seed")?.as_u64()?,
+                timestamp: entry.get("timestamp")?.as_u64()?,
+                has_won: entry.get("won")?.as_bool()?,
+            })
+        })
+        .collect()
+}
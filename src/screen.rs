@@ -0,0 +1,58 @@
+/// The screens of the window frontend.
+#[derive(Clone, Debug, Copy, PartialEq)]
+pub enum Screen {
+    Title,
+    Menu,
+    Playing,
+    Paused,
+    GameOver,
+    Settings,
+    Leaderboard,
+}
+
+/// An entry of one of the menus.
+#[derive(Clone, Debug, Copy, PartialEq)]
+pub enum MenuItem {
+    Play,
+    Leaderboard,
+    Settings,
+    Quit,
+    Resume,
+    Restart,
+    MainMenu,
+}
+
+impl Screen {
+    /// Returns the items of the menu shown on this screen, if it has one.
+    pub fn menu_items(&self) -> &'static [MenuItem] {
+        match self {
+            Screen::Menu => &[
+                MenuItem::Play,
+                MenuItem::Leaderboard,
+                MenuItem::Settings,
+                MenuItem::Quit,
+            ],
+            Screen::Paused => &[
+                MenuItem::Resume,
+                MenuItem::Restart,
+                MenuItem::MainMenu,
+                MenuItem::Quit,
+            ],
+            _ => &[],
+        }
+    }
+}
+
+impl MenuItem {
+    pub fn label(&self) -> &'static str {
+        match self {
+            MenuItem::Play => "Play",
+            MenuItem::Leaderboard => "Leaderboard",
+            MenuItem::Settings => "Settings",
+            MenuItem::Quit => "Quit",
+            MenuItem::Resume => "Resume",
+            MenuItem::Restart => "Restart",
+            MenuItem::MainMenu => "Main Menu",
+        }
+    }
+}
@@ -0,0 +1,478 @@
+use crate::config::{
+    Config, DifficultyCurve, DifficultyMeasure, EdgeMode, FoodType, HudPosition, PowerUpType,
+    FOOD_TYPES, MAX_POINTS, POWER_UP_TYPES,
+};
+use piston::input::Key;
+
+/// How much a single key press changes a color component.
+const COLOR_STEP: f32 = 0.02;
+/// How much a single key press changes the cell width.
+const CELL_W_STEP: f64 = 5.0;
+/// How much a single key press changes the food timeout.
+const FOOD_TIMEOUT_STEP: u64 = 25;
+/// How much a single key press changes the spawn chance of expiring food
+/// and power-ups.
+const SPAWN_CHANCE_STEP: f64 = 0.05;
+/// How much a single key press changes the lifetime of expiring food and
+/// power-ups, and the duration of their effects.
+const LIFETIME_STEP: i64 = 5;
+/// How much a single key press changes the height of the HUD bar.
+const HUD_H_STEP: f64 = 5.0;
+const EDGE_MODES: [EdgeMode; 3] = [EdgeMode::Wrap, EdgeMode::Wall, EdgeMode::Bounce];
+const HUD_POSITIONS: [HudPosition; 3] =
+    [HudPosition::Top, HudPosition::Bottom, HudPosition::Hidden];
+/// How much a single key press changes the delays of the difficulty.
+const DELAY_STEP: f64 = 5.0;
+/// How much a single key press changes the rate of the difficulty.
+const RATE_STEP: f64 = 0.5;
+const DIFFICULTY_CURVES: [DifficultyCurve; 4] = [
+    DifficultyCurve::Constant,
+    DifficultyCurve::Linear,
+    DifficultyCurve::Stepped,
+    DifficultyCurve::Exponential,
+];
+const COMPONENT_NAMES: [&str; 4] = ["R", "G", "B", "A"];
+
+/// A config field that can be edited on the settings screen.
+#[derive(Clone, Debug, Copy, PartialEq)]
+pub enum Setting {
+    ScreenW,
+    ScreenH,
+    CellW,
+    RandomObstacleCount,
+    Seed,
+    Fullscreen,
+    SmoothMovement,
+    EdgeX,
+    EdgeY,
+    FoodTimeout,
+    FoodCount,
+    FoodPoints(FoodType),
+    FoodGrowth(FoodType),
+    FoodWeight(FoodType),
+    FoodMaxCount(FoodType),
+    ExpiringSpawnChance,
+    ExpiringLifetime,
+    ExpiringMaxPoints,
+    ExpiringMinPoints,
+    PowerUpSpawnChance,
+    PowerUpLifetime,
+    PowerUpEnabled(PowerUpType),
+    PowerUpWeight(PowerUpType),
+    PowerUpDuration(PowerUpType),
+    HudPosition,
+    HudH,
+    HudItem(&'static str),
+    DifficultyCurve,
+    DifficultyBasedOn,
+    MaxDelay,
+    MinDelay,
+    DifficultyRate,
+    StepEvery,
+    Color(&'static str),
+}
+
+/// The state of the settings screen: a draft of the config that is only
+/// applied and written to the config file when saved.
+pub struct SettingsEditor {
+    pub draft: Config,
+    pub settings: Vec<Setting>,
+    pub selected: usize,
+    /// The selected component of a color, from red to alpha.
+    pub component: usize,
+    /// The outcome of the last save, shown below the settings.
+    pub status: Option<String>,
+}
+
+impl SettingsEditor {
+    pub fn new(config: Config) -> Self {
+        let mut settings = vec![
+            Setting::ScreenW,
+            Setting::ScreenH,
+            Setting::CellW,
+            Setting::RandomObstacleCount,
+            Setting::Seed,
+            Setting::Fullscreen,
+            Setting::SmoothMovement,
+            Setting::EdgeX,
+            Setting::EdgeY,
+            Setting::FoodTimeout,
+            Setting::FoodCount,
+        ];
+        for food_type in FOOD_TYPES {
+            settings.extend([
+                Setting::FoodPoints(food_type),
+                Setting::FoodGrowth(food_type),
+                Setting::FoodWeight(food_type),
+                Setting::FoodMaxCount(food_type),
+            ]);
+        }
+        settings.extend([
+            Setting::ExpiringSpawnChance,
+            Setting::ExpiringLifetime,
+            Setting::ExpiringMaxPoints,
+            Setting::ExpiringMinPoints,
+            Setting::PowerUpSpawnChance,
+            Setting::PowerUpLifetime,
+        ]);
+        for power_up_type in POWER_UP_TYPES {
+            settings.extend([
+                Setting::PowerUpEnabled(power_up_type),
+                Setting::PowerUpWeight(power_up_type),
+                Setting::PowerUpDuration(power_up_type),
+            ]);
+        }
+        settings.extend([Setting::HudPosition, Setting::HudH]);
+        settings.extend(
+            config
+                .hud_items
+                .items()
+                .iter()
+                .map(|(name, _)| Setting::HudItem(name)),
+        );
+        settings.extend([
+            Setting::DifficultyCurve,
+            Setting::DifficultyBasedOn,
+            Setting::MaxDelay,
+            Setting::MinDelay,
+            Setting::DifficultyRate,
+            Setting::StepEvery,
+        ]);
+        settings.extend(config.colors().iter().map(|(name, _)| Setting::Color(name)));
+
+        SettingsEditor {
+            draft: config,
+            settings,
+            selected: 0,
+            component: 0,
+            status: None,
+        }
+    }
+
+    pub fn selected_setting(&self) -> Setting {
+        self.settings[self.selected]
+    }
+
+    pub fn handle_key_press(&mut self, key: Key) {
+        let setting_count = self.settings.len();
+
+        match key {
+            Key::Up | Key::W => self.selected = (self.selected + setting_count - 1) % setting_count,
+            Key::Down | Key::S => self.selected = (self.selected + 1) % setting_count,
+            Key::Tab => self.component = (self.component + 1) % COMPONENT_NAMES.len(),
+            Key::Left | Key::A => self.adjust(-1),
+            Key::Right | Key::D => self.adjust(1),
+            _ => {}
+        }
+    }
+
+    /// Steps the selected value up or down.
+    fn adjust(&mut self, steps: i32) {
+        let setting = self.selected_setting();
+        let draft = &mut self.draft;
+
+        match setting {
+            // Keep the screen a multiple of the cell width
+            Setting::ScreenW => {
+                draft.screen_w = (draft.screen_w + draft.cell_w * steps as f64).max(draft.cell_w)
+            }
+            Setting::ScreenH => {
+                draft.screen_h = (draft.screen_h + draft.cell_w * steps as f64).max(draft.cell_w)
+            }
+            Setting::CellW => {
+                draft.cell_w = (draft.cell_w + CELL_W_STEP * steps as f64).max(CELL_W_STEP)
+            }
+            Setting::RandomObstacleCount => {
+                draft.random_obstacle_count =
+                    (draft.random_obstacle_count as i64 + steps as i64).max(0) as u32
+            }
+            // Stepping below 0 switches to a random seed for every game
+            Setting::Seed => {
+                draft.seed = match (draft.seed, steps > 0) {
+                    (None, true) => Some(0),
+                    (None, false) | (Some(0), false) => None,
+                    (Some(seed), true) => Some(seed.saturating_add(1)),
+                    (Some(seed), false) => Some(seed - 1),
+                }
+            }
+            Setting::Fullscreen => draft.fullscreen = !draft.fullscreen,
+            Setting::SmoothMovement => draft.smooth_movement = !draft.smooth_movement,
+            Setting::EdgeX => draft.edge_x = next_edge_mode(draft.edge_x, steps),
+            Setting::EdgeY => draft.edge_y = next_edge_mode(draft.edge_y, steps),
+            // Stepping below the first step turns the timeout off
+            Setting::FoodTimeout => {
+                let food_timeout = draft.food_timeout.unwrap_or(0) as i64
+                    + FOOD_TIMEOUT_STEP as i64 * steps as i64;
+                draft.food_timeout = match food_timeout > 0 {
+                    true => Some(food_timeout as u64),
+                    false => None,
+                }
+            }
+            Setting::FoodCount => {
+                draft.foods.count = (draft.foods.count as i64 + steps as i64).max(1) as u32
+            }
+            Setting::FoodPoints(food_type) => {
+                let kind = draft.foods.kind_mut(food_type);
+                kind.points = step_points(kind.points, steps)
+            }
+            Setting::FoodGrowth(food_type) => {
+                let kind = draft.foods.kind_mut(food_type);
+                kind.growth = kind.growth.saturating_add(steps)
+            }
+            Setting::FoodWeight(food_type) => {
+                let kind = draft.foods.kind_mut(food_type);
+                kind.weight = (kind.weight as i64 + steps as i64).max(0) as u32
+            }
+            Setting::FoodMaxCount(food_type) => {
+                let kind = draft.foods.kind_mut(food_type);
+                kind.max_count = (kind.max_count as i64 + steps as i64).max(0) as u32
+            }
+            Setting::ExpiringSpawnChance => {
+                let expiring = &mut draft.foods.expiring;
+                let chance =
+                    (expiring.spawn_chance + SPAWN_CHANCE_STEP * steps as f64).clamp(0.0, 1.0);
+                expiring.spawn_chance = (chance * 100.0).round() / 100.0;
+            }
+            Setting::ExpiringLifetime => {
+                let expiring = &mut draft.foods.expiring;
+                expiring.lifetime =
+                    (expiring.lifetime as i64 + LIFETIME_STEP * steps as i64).max(1) as u64
+            }
+            Setting::ExpiringMaxPoints => {
+                let expiring = &mut draft.foods.expiring;
+                expiring.max_points = step_points(expiring.max_points, steps)
+            }
+            Setting::ExpiringMinPoints => {
+                let expiring = &mut draft.foods.expiring;
+                expiring.min_points = step_points(expiring.min_points, steps)
+            }
+            Setting::PowerUpSpawnChance => {
+                let power_ups = &mut draft.power_ups;
+                let chance =
+                    (power_ups.spawn_chance + SPAWN_CHANCE_STEP * steps as f64).clamp(0.0, 1.0);
+                power_ups.spawn_chance = (chance * 100.0).round() / 100.0;
+            }
+            Setting::PowerUpLifetime => {
+                let power_ups = &mut draft.power_ups;
+                power_ups.lifetime =
+                    (power_ups.lifetime as i64 + LIFETIME_STEP * steps as i64).max(1) as u64
+            }
+            Setting::PowerUpEnabled(power_up_type) => {
+                let kind = draft.power_ups.kind_mut(power_up_type);
+                kind.enabled = !kind.enabled
+            }
+            Setting::PowerUpWeight(power_up_type) => {
+                let kind = draft.power_ups.kind_mut(power_up_type);
+                kind.weight = (kind.weight as i64 + steps as i64).max(0) as u32
+            }
+            Setting::PowerUpDuration(power_up_type) => {
+                let kind = draft.power_ups.kind_mut(power_up_type);
+                kind.duration = (kind.duration as i64 + LIFETIME_STEP * steps as i64).max(1) as u64
+            }
+            Setting::HudPosition => {
+                let index = HUD_POSITIONS
+                    .iter()
+                    .position(|position| *position == draft.hud_position)
+                    .unwrap_or(0) as i32;
+                let count = HUD_POSITIONS.len() as i32;
+                draft.hud_position = HUD_POSITIONS[(index + steps).rem_euclid(count) as usize];
+            }
+            Setting::HudH => {
+                draft.hud_h = (draft.hud_h + HUD_H_STEP * steps as f64).max(HUD_H_STEP)
+            }
+            Setting::HudItem(name) => {
+                if let Some(is_shown) = draft.hud_items.item_mut(name) {
+                    *is_shown = !*is_shown;
+                }
+            }
+            Setting::DifficultyCurve => {
+                let difficulty = &mut draft.difficulty;
+                let index = DIFFICULTY_CURVES
+                    .iter()
+                    .position(|curve| *curve == difficulty.curve)
+                    .unwrap_or(0) as i32;
+                let count = DIFFICULTY_CURVES.len() as i32;
+                difficulty.curve = DIFFICULTY_CURVES[(index + steps).rem_euclid(count) as usize];
+            }
+            Setting::DifficultyBasedOn => {
+                draft.difficulty.based_on = match draft.difficulty.based_on {
+                    DifficultyMeasure::Score => DifficultyMeasure::Length,
+                    DifficultyMeasure::Length => DifficultyMeasure::Score,
+                }
+            }
+            Setting::MaxDelay => {
+                let difficulty = &mut draft.difficulty;
+                difficulty.max_delay =
+                    (difficulty.max_delay + DELAY_STEP * steps as f64).max(DELAY_STEP)
+            }
+            Setting::MinDelay => {
+                let difficulty = &mut draft.difficulty;
+                difficulty.min_delay =
+                    (difficulty.min_delay + DELAY_STEP * steps as f64).max(DELAY_STEP)
+            }
+            Setting::DifficultyRate => {
+                let difficulty = &mut draft.difficulty;
+                difficulty.rate = (difficulty.rate + RATE_STEP * steps as f64).max(0.0)
+            }
+            Setting::StepEvery => {
+                let difficulty = &mut draft.difficulty;
+                difficulty.step_every = (difficulty.step_every as i64 + steps as i64).max(1) as u32
+            }
+            Setting::Color(name) => {
+                if let Some(color) = draft.color_mut(name) {
+                    let component = &mut color[self.component];
+                    let value = (*component + COLOR_STEP * steps as f32).clamp(0.0, 1.0);
+                    // Round off the float noise, so the saved file stays readable
+                    *component = (value * 1000.0).round() / 1000.0;
+                }
+            }
+        }
+
+        self.status = None;
+    }
+
+    /// Returns the line shown for the setting, marking the selected
+    /// component of a selected color.
+    pub fn setting_text(&self, index: usize) -> String {
+        let draft = &self.draft;
+
+        let value = match self.settings[index] {
+            Setting::ScreenW => draft.screen_w.to_string(),
+            Setting::ScreenH => draft.screen_h.to_string(),
+            Setting::CellW => draft.cell_w.to_string(),
+            Setting::RandomObstacleCount => draft.random_obstacle_count.to_string(),
+            Setting::Seed => match draft.seed {
+                Some(seed) => seed.to_string(),
+                None => String::from("random"),
+            },
+            Setting::Fullscreen => draft.fullscreen.to_string(),
+            Setting::SmoothMovement => draft.smooth_movement.to_string(),
+            Setting::EdgeX => format!("{:?}", draft.edge_x).to_lowercase(),
+            Setting::EdgeY => format!("{:?}", draft.edge_y).to_lowercase(),
+            Setting::FoodTimeout => match draft.food_timeout {
+                Some(food_timeout) => food_timeout.to_string(),
+                None => String::from("off"),
+            },
+            Setting::FoodCount => draft.foods.count.to_string(),
+            Setting::FoodPoints(food_type) => draft.foods.kind(food_type).points.to_string(),
+            Setting::FoodGrowth(food_type) => draft.foods.kind(food_type).growth.to_string(),
+            Setting::FoodWeight(food_type) => draft.foods.kind(food_type).weight.to_string(),
+            Setting::FoodMaxCount(food_type) => draft.foods.kind(food_type).max_count.to_string(),
+            Setting::ExpiringSpawnChance => draft.foods.expiring.spawn_chance.to_string(),
+            Setting::ExpiringLifetime => draft.foods.expiring.lifetime.to_string(),
+            Setting::ExpiringMaxPoints => draft.foods.expiring.max_points.to_string(),
+            Setting::ExpiringMinPoints => draft.foods.expiring.min_points.to_string(),
+            Setting::PowerUpSpawnChance => draft.power_ups.spawn_chance.to_string(),
+            Setting::PowerUpLifetime => draft.power_ups.lifetime.to_string(),
+            Setting::PowerUpEnabled(power_up_type) => {
+                draft.power_ups.kind(power_up_type).enabled.to_string()
+            }
+            Setting::PowerUpWeight(power_up_type) => {
+                draft.power_ups.kind(power_up_type).weight.to_string()
+            }
+            Setting::PowerUpDuration(power_up_type) => {
+                draft.power_ups.kind(power_up_type).duration.to_string()
+            }
+            Setting::HudPosition => format!("{:?}", draft.hud_position).to_lowercase(),
+            Setting::HudH => draft.hud_h.to_string(),
+            Setting::HudItem(name) => draft
+                .hud_items
+                .items()
+                .iter()
+                .find(|(item_name, _)| *item_name == name)
+                .map(|(_, is_shown)| *is_shown)
+                .unwrap_or_default()
+                .to_string(),
+            Setting::DifficultyCurve => format!("{:?}", draft.difficulty.curve).to_lowercase(),
+            Setting::DifficultyBasedOn => format!("{:?}", draft.difficulty.based_on).to_lowercase(),
+            Setting::MaxDelay => draft.difficulty.max_delay.to_string(),
+            Setting::MinDelay => draft.difficulty.min_delay.to_string(),
+            Setting::DifficultyRate => draft.difficulty.rate.to_string(),
+            Setting::StepEvery => draft.difficulty.step_every.to_string(),
+            Setting::Color(name) => {
+                let color = draft
+                    .colors()
+                    .iter()
+                    .find(|(color_name, _)| *color_name == name)
+                    .map(|(_, color)| *color)
+                    .unwrap_or_default();
+
+                let components: Vec<String> = color
+                    .iter()
+                    .zip(COMPONENT_NAMES.iter())
+                    .enumerate()
+                    .map(|(component, (value, component_name))| {
+                        match index == self.selected && component == self.component {
+                            true => format!("[{} {:.2}]", component_name, value),
+                            false => format!("{} {:.2}", component_name, value),
+                        }
+                    })
+                    .collect();
+
+                components.join(" ")
+            }
+        };
+
+        format!("{}: {}", setting_name(self.settings[index]), value)
+    }
+}
+
+pub fn setting_name(setting: Setting) -> String {
+    match setting {
+        Setting::ScreenW => String::from("screen_w"),
+        Setting::ScreenH => String::from("screen_h"),
+        Setting::CellW => String::from("cell_w"),
+        Setting::RandomObstacleCount => String::from("random_obstacle_count"),
+        Setting::Seed => String::from("seed"),
+        Setting::Fullscreen => String::from("fullscreen"),
+        Setting::SmoothMovement => String::from("smooth_movement"),
+        Setting::EdgeX => String::from("edge_x"),
+        Setting::EdgeY => String::from("edge_y"),
+        Setting::FoodTimeout => String::from("food_timeout"),
+        Setting::FoodCount => String::from("foods.count"),
+        Setting::FoodPoints(food_type) => format!("foods.{}.points", food_type.name()),
+        Setting::FoodGrowth(food_type) => format!("foods.{}.growth", food_type.name()),
+        Setting::FoodWeight(food_type) => format!("foods.{}.weight", food_type.name()),
+        Setting::FoodMaxCount(food_type) => format!("foods.{}.max_count", food_type.name()),
+        Setting::ExpiringSpawnChance => String::from("foods.expiring.spawn_chance"),
+        Setting::ExpiringLifetime => String::from("foods.expiring.lifetime"),
+        Setting::ExpiringMaxPoints => String::from("foods.expiring.max_points"),
+        Setting::ExpiringMinPoints => String::from("foods.expiring.min_points"),
+        Setting::PowerUpSpawnChance => String::from("power_ups.spawn_chance"),
+        Setting::PowerUpLifetime => String::from("power_ups.lifetime"),
+        Setting::PowerUpEnabled(power_up_type) => {
+            format!("power_ups.{}.enabled", power_up_type.name())
+        }
+        Setting::PowerUpWeight(power_up_type) => {
+            format!("power_ups.{}.weight", power_up_type.name())
+        }
+        Setting::PowerUpDuration(power_up_type) => {
+            format!("power_ups.{}.duration", power_up_type.name())
+        }
+        Setting::HudPosition => String::from("hud_position"),
+        Setting::HudH => String::from("hud_h"),
+        Setting::HudItem(name) => format!("hud_items.{}", name),
+        Setting::DifficultyCurve => String::from("difficulty.curve"),
+        Setting::DifficultyBasedOn => String::from("difficulty.based_on"),
+        Setting::MaxDelay => String::from("difficulty.max_delay"),
+        Setting::MinDelay => String::from("difficulty.min_delay"),
+        Setting::DifficultyRate => String::from("difficulty.rate"),
+        Setting::StepEvery => String::from("difficulty.step_every"),
+        Setting::Color(name) => String::from(name),
+    }
+}
+
+/// Steps the points of a food, keeping them within what the score can take.
+fn step_points(points: u16, steps: i32) -> u16 {
+    (points as i32 + steps).clamp(0, MAX_POINTS as i32) as u16
+}
+
+/// Returns the edge mode the given number of steps away, wrapping around.
+fn next_edge_mode(edge_mode: EdgeMode, steps: i32) -> EdgeMode {
+    let index = EDGE_MODES
+        .iter()
+        .position(|mode| *mode == edge_mode)
+        .unwrap_or(0) as i32;
+    let count = EDGE_MODES.len() as i32;
+    EDGE_MODES[(index + steps).rem_euclid(count) as usize]
+}
@@ -0,0 +1,1033 @@
+use crate::config::{
+    Config, DifficultyMeasure, FoodType, PowerUpType, FOOD_TYPES, INITIAL_SNAKE_LENGTH,
+    POWER_UP_TYPES,
+};
+use crate::grid::OccupancyGrid;
+use crate::replay::{Input, InputEvent, Replay};
+use crate::snake::{Direction, Milliseconds, Node, Snake};
+use piston::input::Key;
+use rand::rngs::StdRng;
+use rand::{thread_rng, Rng, SeedableRng};
+use std::collections::VecDeque;
+use std::fmt;
+
+/// The distance in cells from the head the magnet pulls food from.
+const MAGNET_RANGE: i32 = 5;
+
+/// A food on the board.
+#[derive(Clone, Debug, Copy, PartialEq)]
+pub struct Food {
+    pub food_type: FoodType,
+    pub cell: Node,
+    /// The tick the food disappears on, if it is an expiring one.
+    pub expires_at: Option<u64>,
+}
+
+/// A power-up waiting on the board to be picked up.
+#[derive(Clone, Debug, Copy, PartialEq)]
+pub struct PowerUp {
+    pub power_up_type: PowerUpType,
+    pub cell: Node,
+    /// The tick the pickup disappears on.
+    pub expires_at: u64,
+}
+
+/// What the snake died of.
+#[derive(Clone, Debug, Copy, PartialEq)]
+pub enum DeathCause {
+    /// The head ran into the body, `segment` counts from the head,
+    /// so the neck is segment 1.
+    SelfCollision { segment: usize, cell: Node },
+    /// The head ran into the obstacle on `cell`.
+    Obstacle { cell: Node },
+    /// The head on `cell` ran into a wall edge.
+    Wall { cell: Node },
+    /// The snake made `moves` moves without eating, see `food_timeout`.
+    Timeout { moves: u64 },
+}
+
+impl DeathCause {
+    /// The cell the game ended on, if there is one.
+    pub fn cell(&self) -> Option<Node> {
+        match *self {
+            DeathCause::SelfCollision { cell, .. }
+            | DeathCause::Obstacle { cell }
+            | DeathCause::Wall { cell } => Some(cell),
+            DeathCause::Timeout { .. } => None,
+        }
+    }
+}
+
+impl fmt::Display for DeathCause {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DeathCause::SelfCollision { segment, cell } => write!(
+                f,
+                "Bit its own segment {} at {}, {}",
+                segment, cell.x, cell.y
+            ),
+            DeathCause::Obstacle { cell } => {
+                write!(f, "Hit an obstacle at {}, {}", cell.x, cell.y)
+            }
+            DeathCause::Wall { cell } => write!(f, "Hit the wall at {}, {}", cell.x, cell.y),
+            DeathCause::Timeout { moves } => write!(f, "Starved after {} moves", moves),
+        }
+    }
+}
+
+/// The rules of the game without any window or rendering attached to it,
+/// so it can be driven by a frontend, a test or a tool alike.
+pub struct Simulation {
+    pub config: Config,
+    pub snake: Snake,
+    pub foods: Vec<Food>,
+    pub power_ups: Vec<PowerUp>,
+    pub obstacles: Vec<Node>,
+    pub grid: OccupancyGrid,
+    pub score: u16,
+    pub high_score: u16,
+    /// Whether the last game ended by filling the whole board.
+    pub has_won: bool,
+    /// What ended the last game, unless it was won.
+    pub death_cause: Option<DeathCause>,
+    /// The number of games won since the start.
+    pub wins: u32,
+    pub is_paused: bool,
+    pub direction_queue: VecDeque<Direction>,
+    /// The cell the tail has left on the last move, so it can be animated.
+    pub previous_tail: Option<Node>,
+    /// The seed the current game was started with.
+    pub seed: u64,
+    /// The number of movement ticks since the game has started.
+    pub tick: u64,
+    /// The number of moves since the snake has last eaten.
+    pub moves_since_food: u64,
+    /// The time played in the current game, without the pauses.
+    pub elapsed: Milliseconds,
+    /// The inputs of the current game, so it can be saved as a replay.
+    pub recording: Replay,
+    /// A config that replaces the current one on the next `reset_game`.
+    pub pending_config: Option<Config>,
+    playback: Option<Replay>,
+    playback_index: usize,
+    rng: StdRng,
+}
+
+impl Simulation {
+    pub fn new(config: Config) -> Self {
+        // Create the snake
+        let snake = Snake {
+            nodes: VecDeque::new(),
+            direction: Direction::Left,
+            is_alive: true,
+            is_turbo: false,
+            movement_delay: config.difficulty.max_delay,
+            last_movement_duration: 0.0,
+            pending_growth: 0,
+            effects: Vec::new(),
+        };
+
+        let mut simulation = Simulation {
+            config,
+            snake,
+            foods: Vec::new(),
+            power_ups: Vec::new(),
+            obstacles: Vec::new(),
+            grid: OccupancyGrid::new(config.grid_w(), config.grid_h()),
+            score: 0,
+            high_score: 0,
+            has_won: false,
+            death_cause: None,
+            wins: 0,
+            is_paused: false,
+            direction_queue: VecDeque::new(),
+            previous_tail: None,
+            seed: 0,
+            tick: 0,
+            moves_since_food: 0,
+            elapsed: 0.0,
+            recording: Replay::new(0, config),
+            pending_config: None,
+            playback: None,
+            playback_index: 0,
+            rng: StdRng::seed_from_u64(0),
+        };
+
+        simulation.reset_game();
+        simulation
+    }
+
+    /// Creates a simulation that plays back the inputs of the given replay
+    /// instead of listening to the keyboard.
+    pub fn from_replay(replay: Replay) -> Self {
+        let mut config = replay.config;
+        config.seed = Some(replay.seed);
+
+        let mut simulation = Self::new(config);
+        simulation.playback = Some(replay);
+        simulation
+    }
+
+    pub fn is_replay(&self) -> bool {
+        self.playback.is_some()
+    }
+
+    /// Advances the clock by `dt_ms` and runs a movement tick for every
+    /// movement delay that has elapsed, so fast games and fast-forwarded
+    /// replays aren't capped by the frame rate.
+    pub fn update(&mut self, dt_ms: Milliseconds) {
+        if self.is_paused {
+            return;
+        }
+
+        if !self.snake.is_alive {
+            self.snake.last_movement_duration = 0.0;
+            return;
+        }
+
+        self.elapsed += dt_ms;
+        self.snake.last_movement_duration += dt_ms;
+
+        while self.snake.is_alive && self.snake.last_movement_duration >= self.movement_delay() {
+            // Keep the overshoot, so the next move isn't late by a frame
+            self.snake.last_movement_duration -= self.movement_delay();
+            self.step();
+        }
+    }
+
+    /// The time between two moves of the snake.
+    pub fn movement_delay(&self) -> Milliseconds {
+        let movement_delay = match self.snake.is_turbo {
+            true => self.snake.movement_delay / 2.0,
+            false => self.snake.movement_delay,
+        };
+
+        match self.snake.has_effect(PowerUpType::SlowMotion) {
+            true => movement_delay * 2.0,
+            false => movement_delay,
+        }
+    }
+
+    /// Returns the score or the growth of the snake, whichever the
+    /// difficulty is based on.
+    pub fn difficulty_progress(&self) -> u32 {
+        match self.config.difficulty.based_on {
+            DifficultyMeasure::Score => self.score as u32,
+            DifficultyMeasure::Length => (self.snake.nodes.len() as u32
+                + self.snake.pending_growth)
+                .saturating_sub(INITIAL_SNAKE_LENGTH as u32),
+        }
+    }
+
+    pub fn level(&self) -> u32 {
+        self.config.difficulty.level(self.difficulty_progress())
+    }
+
+    /// Advances the game by exactly one movement tick.
+    pub fn step(&mut self) {
+        if !self.snake.is_alive {
+            return;
+        }
+
+        // Feed the inputs that were recorded for this tick
+        if let Some(playback) = &self.playback {
+            let mut inputs = Vec::new();
+            while let Some(event) = playback.inputs.get(self.playback_index) {
+                if event.tick > self.tick {
+                    break;
+                }
+                inputs.push(event.input);
+                self.playback_index += 1;
+            }
+
+            for input in inputs {
+                self.apply_input(input);
+            }
+        }
+
+        self.tick += 1;
+
+        // Check if there is a new direction in the input direction queue
+        if let Some(key) = self.direction_queue.pop_front() {
+            match (key, &self.snake.direction) {
+                (Direction::Up, Direction::Down) => {}
+                (Direction::Down, Direction::Up) => {}
+                (Direction::Right, Direction::Left) => {}
+                (Direction::Left, Direction::Right) => {}
+
+                (Direction::Up, _) => self.snake.direction = Direction::Up,
+                (Direction::Down, _) => self.snake.direction = Direction::Down,
+                (Direction::Right, _) => self.snake.direction = Direction::Right,
+                (Direction::Left, _) => self.snake.direction = Direction::Left,
+            }
+        }
+
+        // Update the snakes location
+        let (head, left_tail) = match self.snake.update_node_locations(self.config) {
+            Some(moved) => moved,
+            None => {
+                self.previous_tail = None;
+                let cell = *self.snake.nodes.front().unwrap();
+                self.die(DeathCause::Wall { cell });
+                return;
+            }
+        };
+
+        if let Some(tail) = left_tail {
+            self.grid.remove_snake(tail);
+        }
+        self.previous_tail = left_tail;
+
+        // Check if the snake did bite itself, a ghost passes through instead
+        if self.grid.is_snake(head) && !self.snake.has_effect(PowerUpType::Ghost) {
+            let segment = self
+                .snake
+                .nodes
+                .iter()
+                .skip(1)
+                .position(|node| *node == head)
+                .map_or(0, |index| index + 1);
+            self.die(DeathCause::SelfCollision {
+                segment,
+                cell: head,
+            });
+            return;
+        }
+
+        // Check if the snake hit an obstacle, a shield breaks it instead
+        if self.grid.is_obstacle(head) {
+            if !self.snake.has_effect(PowerUpType::Shield) {
+                self.die(DeathCause::Obstacle { cell: head });
+                return;
+            }
+
+            self.snake.remove_effect(PowerUpType::Shield);
+            self.obstacles.retain(|obstacle| *obstacle != head);
+            self.grid.set_obstacle(head, false);
+        }
+
+        self.grid.add_snake(head);
+
+        self.moves_since_food += 1;
+
+        // The effects of this move are used up, before new ones are picked up
+        self.snake.tick_effects();
+        self.pick_up_power_up(head);
+
+        // Check if the snake has eaten a food
+        let eaten_index = match self.grid.is_food(head) {
+            true => self.foods.iter().position(|food| food.cell == head),
+            false => None,
+        };
+
+        if let Some(index) = eaten_index {
+            let food = self.foods.remove(index);
+            let kind = *self.config.foods.kind(food.food_type);
+
+            // Expiring food is worth more the faster it was reached
+            let points = match food.expires_at {
+                Some(expires_at) => self.config.foods.expiring.points(expires_at - self.tick),
+                None => kind.points,
+            };
+
+            self.moves_since_food = 0;
+            if self.snake.is_turbo {
+                self.score = self.score.saturating_add(points.saturating_mul(2));
+            } else {
+                self.score = self.score.saturating_add(points);
+            }
+
+            if kind.growth >= 0 {
+                // Let the tail stay in place on the next moves
+                self.snake.pending_growth += kind.growth as u32;
+            } else {
+                self.shrink(kind.growth.unsigned_abs());
+            }
+
+            self.snake.movement_delay = self
+                .config
+                .difficulty
+                .movement_delay(self.difficulty_progress());
+            self.grid.set_food(head, false);
+            self.place_foods();
+        }
+
+        self.update_expiring_food();
+        self.update_power_ups();
+
+        // The snake and the obstacles take every cell, so the board is full.
+        // A food or a pickup still leaves a cell for the snake to grow into
+        if self.grid.free_count() == 0 && self.foods.is_empty() && self.power_ups.is_empty() {
+            self.win();
+            return;
+        }
+
+        // End the game if the snake went too long without eating
+        if let Some(food_timeout) = self.config.food_timeout {
+            if self.moves_since_food >= food_timeout {
+                self.die(DeathCause::Timeout {
+                    moves: self.moves_since_food,
+                });
+            }
+        }
+    }
+
+    pub fn handle_key_press(&mut self, key: Key) {
+        if !self.snake.is_alive {
+            if key == Key::Space {
+                self.reset_game();
+            }
+        } else if key == Key::P || key == Key::Escape {
+            self.is_paused = !self.is_paused;
+        } else if self.is_paused || self.is_replay() {
+            // Ignore the inputs while paused, or when they come from the replay
+        } else {
+            // Check for the turbo key
+            if key == Key::LShift {
+                self.apply_input(Input::Turbo(true));
+            }
+
+            match key {
+                Key::Up | Key::W => self.apply_input(Input::Direction(Direction::Up)),
+                Key::Down | Key::S => self.apply_input(Input::Direction(Direction::Down)),
+                Key::Right | Key::D => self.apply_input(Input::Direction(Direction::Right)),
+                Key::Left | Key::A => self.apply_input(Input::Direction(Direction::Left)),
+                _ => {}
+            }
+        }
+    }
+
+    pub fn handle_key_release(&mut self, key: Key) {
+        if self.snake.is_alive && !self.is_replay() && key == Key::LShift {
+            self.apply_input(Input::Turbo(false));
+        }
+    }
+
+    /// Pauses the game, unless it is already over.
+    pub fn pause(&mut self) {
+        if self.snake.is_alive {
+            self.is_paused = true;
+        }
+    }
+
+    /// Applies the colors of the given config right away, and the rest of
+    /// it, which could change the board, on the next `reset_game`.
+    pub fn apply_config(&mut self, config: Config) {
+        self.apply_colors(&config);
+        self.pending_config = Some(config);
+    }
+
+    pub fn apply_colors(&mut self, config: &Config) {
+        for (name, color) in config.colors() {
+            if let Some(current_color) = self.config.color_mut(name) {
+                *current_color = color;
+            }
+        }
+    }
+
+    /// Applies an input to the snake and records it for the current tick.
+    pub fn apply_input(&mut self, input: Input) {
+        match input {
+            Input::Direction(direction) => self.direction_queue.push_back(direction),
+            Input::Turbo(is_turbo) => self.snake.is_turbo = is_turbo,
+        }
+
+        self.recording.inputs.push(InputEvent {
+            tick: self.tick,
+            input,
+        });
+    }
+
+    pub fn place_random_obstacles(&mut self, count: u32) {
+        for _ in 0..count {
+            match self.find_random_available_node() {
+                Some(node) => {
+                    self.obstacles.push(node);
+                    self.grid.set_obstacle(node, true);
+                }
+                None => break,
+            }
+        }
+    }
+
+    /// Tops the food on the board up to `foods.count`, picking the kind of
+    /// every new food by weight. Returns false if there was no free cell.
+    pub fn place_foods(&mut self) -> bool {
+        // Expiring food comes on top of the count
+        while (self
+            .foods
+            .iter()
+            .filter(|food| food.expires_at.is_none())
+            .count() as u32)
+            < self.config.foods.count
+        {
+            // Only the kinds below their max count can be picked
+            let choices: Vec<(FoodType, u32)> = FOOD_TYPES
+                .iter()
+                .copied()
+                .filter(|food_type| {
+                    let kind = self.config.foods.kind(*food_type);
+                    let count = self
+                        .foods
+                        .iter()
+                        .filter(|food| food.expires_at.is_none() && food.food_type == *food_type)
+                        .count() as u32;
+                    kind.weight > 0 && count < kind.max_count
+                })
+                .map(|food_type| (food_type, self.config.foods.kind(food_type).weight))
+                .collect();
+
+            // Only roll when there is a choice, so a game with just normal
+            // food uses the same random numbers as before
+            let food_type = match choices.len() {
+                0 => break,
+                1 => choices[0].0,
+                _ => self.pick_weighted(&choices),
+            };
+
+            if !self.place_random_food(food_type, None) {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    /// Places a food of the given kind on a free cell, returning false if
+    /// there was none. A food with a lifetime disappears after that many
+    /// moves.
+    pub fn place_random_food(&mut self, food_type: FoodType, lifetime: Option<u64>) -> bool {
+        match self.find_random_available_node() {
+            Some(cell) => {
+                self.foods.push(Food {
+                    food_type,
+                    cell,
+                    expires_at: lifetime.map(|lifetime| self.tick + lifetime),
+                });
+                self.grid.set_food(cell, true);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Removes the expiring food that wasn't eaten in time, tops up the
+    /// food that found no room before, and rolls for a new expiring food
+    /// when there is none.
+    fn update_expiring_food(&mut self) {
+        let tick = self.tick;
+        let grid = &mut self.grid;
+        self.foods.retain(|food| match food.expires_at {
+            Some(expires_at) if expires_at <= tick => {
+                grid.set_food(food.cell, false);
+                false
+            }
+            _ => true,
+        });
+
+        // The last free cells may have been taken by expiring food or
+        // pickups, which leaves the board short of food until they are gone
+        self.place_foods();
+
+        // Don't roll at all when it is off, so the other food stays the same
+        let expiring = self.config.foods.expiring;
+        if expiring.spawn_chance <= 0.0 || self.foods.iter().any(|food| food.expires_at.is_some()) {
+            return;
+        }
+
+        if self.rng.gen::<f64>() < expiring.spawn_chance {
+            self.place_random_food(FoodType::Bonus, Some(expiring.lifetime));
+        }
+    }
+
+    /// Picks one of the choices at random, weighted by the number next to it.
+    fn pick_weighted<T: Copy>(&mut self, choices: &[(T, u32)]) -> T {
+        let total_weight: u32 = choices.iter().map(|(_, weight)| weight).sum();
+        let mut roll = self.rng.gen_range(0, total_weight);
+
+        for (choice, weight) in choices {
+            if roll < *weight {
+                return *choice;
+            }
+            roll -= weight;
+        }
+
+        choices[choices.len() - 1].0
+    }
+
+    /// Starts the effect of the power-up on the given cell, if there is one.
+    fn pick_up_power_up(&mut self, cell: Node) {
+        if !self.grid.is_power_up(cell) {
+            return;
+        }
+
+        if let Some(index) = self
+            .power_ups
+            .iter()
+            .position(|power_up| power_up.cell == cell)
+        {
+            let power_up = self.power_ups.remove(index);
+            let duration = self.config.power_ups.kind(power_up.power_up_type).duration;
+            self.snake.add_effect(power_up.power_up_type, duration);
+        }
+
+        self.grid.set_power_up(cell, false);
+    }
+
+    /// Removes the pickups that weren't picked up in time, tops up the food
+    /// on their cells, pulls the food with the magnet, and rolls for a new
+    /// pickup when there is none.
+    fn update_power_ups(&mut self) {
+        let tick = self.tick;
+        let grid = &mut self.grid;
+        self.power_ups.retain(|power_up| {
+            if power_up.expires_at <= tick {
+                grid.set_power_up(power_up.cell, false);
+                return false;
+            }
+            true
+        });
+        self.place_foods();
+
+        if self.snake.has_effect(PowerUpType::Magnet) {
+            self.pull_food();
+        }
+
+        // Don't roll at all when they are off, so the food stays the same
+        let power_ups = self.config.power_ups;
+        if power_ups.spawn_chance <= 0.0 || !self.power_ups.is_empty() {
+            return;
+        }
+
+        let choices: Vec<(PowerUpType, u32)> = POWER_UP_TYPES
+            .iter()
+            .map(|power_up_type| (*power_up_type, power_ups.kind(*power_up_type)))
+            .filter(|(_, kind)| kind.enabled && kind.weight > 0)
+            .map(|(power_up_type, kind)| (power_up_type, kind.weight))
+            .collect();
+
+        if choices.is_empty() || self.rng.gen::<f64>() >= power_ups.spawn_chance {
+            return;
+        }
+
+        let power_up_type = self.pick_weighted(&choices);
+        if let Some(cell) = self.find_random_available_node() {
+            self.power_ups.push(PowerUp {
+                power_up_type,
+                cell,
+                expires_at: self.tick + power_ups.lifetime,
+            });
+            self.grid.set_power_up(cell, true);
+        }
+    }
+
+    /// Moves every food close to the head a single cell towards it, along
+    /// the longer distance, if that cell is free.
+    fn pull_food(&mut self) {
+        let head = *self.snake.nodes.front().unwrap();
+
+        for food in self.foods.iter_mut() {
+            let x_distance = head.x - food.cell.x;
+            let y_distance = head.y - food.cell.y;
+            if x_distance.abs().max(y_distance.abs()) > MAGNET_RANGE {
+                continue;
+            }
+
+            let target = match x_distance.abs() >= y_distance.abs() {
+                true => Node {
+                    x: food.cell.x + x_distance.signum(),
+                    y: food.cell.y,
+                },
+                false => Node {
+                    x: food.cell.x,
+                    y: food.cell.y + y_distance.signum(),
+                },
+            };
+
+            if self.grid.is_free(target) {
+                self.grid.set_food(food.cell, false);
+                self.grid.set_food(target, true);
+                food.cell = target;
+            }
+        }
+    }
+
+    /// Removes cells from the tail, taking back the growth that is still
+    /// to come first. The snake never gets shorter than at the start.
+    fn shrink(&mut self, count: u32) {
+        let taken_back = count.min(self.snake.pending_growth);
+        self.snake.pending_growth -= taken_back;
+
+        for _ in taken_back..count {
+            if self.snake.nodes.len() <= INITIAL_SNAKE_LENGTH as usize {
+                break;
+            }
+
+            if let Some(tail) = self.snake.nodes.pop_back() {
+                self.grid.remove_snake(tail);
+            }
+        }
+
+        // The tail jumped, so there is nothing to slide out of
+        self.previous_tail = None;
+    }
+
+    fn find_random_available_node(&mut self) -> Option<Node> {
+        let free_count = self.grid.free_count();
+        if free_count == 0 {
+            return None;
+        }
+
+        let index = self.rng.gen_range(0, free_count);
+        self.grid.nth_free(index)
+    }
+
+    fn game_over(&mut self) {
+        if self.score > self.high_score {
+            self.high_score = self.score;
+        }
+
+        self.snake.is_alive = false;
+    }
+
+    fn die(&mut self, cause: DeathCause) {
+        self.death_cause = Some(cause);
+        self.game_over();
+    }
+
+    fn win(&mut self) {
+        self.game_over();
+        self.has_won = true;
+        self.wins += 1;
+    }
+
+    pub fn reset_game(&mut self) {
+        if let Some(config) = self.pending_config.take() {
+            self.config = config;
+        }
+
+        // Create the snake in the middle of the grid
+        let x = (self.config.grid_w() / 2).min(self.config.grid_w() - INITIAL_SNAKE_LENGTH);
+        let y = self.config.grid_h() / 2;
+        let nodes: VecDeque<Node> = (0..INITIAL_SNAKE_LENGTH)
+            .map(|i| Node { x: x + i, y })
+            .collect();
+
+        self.grid = OccupancyGrid::new(self.config.grid_w(), self.config.grid_h());
+        for node in nodes.iter() {
+            self.grid.add_snake(*node);
+        }
+
+        self.snake.nodes = nodes;
+        self.snake.pending_growth = 0;
+        self.snake.effects.clear();
+        self.snake.direction = Direction::Left;
+        self.snake.is_alive = true;
+        self.snake.is_turbo = false;
+        self.snake.last_movement_duration = 0.0;
+
+        // Use the configured seed so the layout can be reproduced,
+        // otherwise pick a fresh one for every game
+        self.seed = match self.config.seed {
+            Some(seed) => seed,
+            None => thread_rng().gen(),
+        };
+        self.rng = StdRng::seed_from_u64(self.seed);
+
+        self.tick = 0;
+        self.moves_since_food = 0;
+        self.elapsed = 0.0;
+        self.recording = Replay::new(self.seed, self.config);
+        self.playback_index = 0;
+        self.direction_queue.clear();
+        self.previous_tail = None;
+
+        self.score = 0;
+        self.snake.movement_delay = self.config.difficulty.movement_delay(0);
+        self.has_won = false;
+        self.death_cause = None;
+        self.is_paused = false;
+        self.foods.clear();
+        self.power_ups.clear();
+        self.obstacles = Vec::new();
+
+        self.place_random_obstacles(self.config.random_obstacle_count);
+        self.place_foods();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{EdgeMode, HudPosition, MAX_POINTS};
+
+    /// A config without obstacles or food, so every move is under control.
+    fn empty_config() -> Config {
+        let mut config = Config::new();
+        config.random_obstacle_count = 0;
+        config.foods.count = 0;
+        config.seed = Some(7);
+        config
+    }
+
+    fn place_snake(simulation: &mut Simulation, cells: &[(i32, i32)], direction: Direction) {
+        for node in simulation.snake.nodes.drain(..) {
+            simulation.grid.remove_snake(node);
+        }
+        for &(x, y) in cells {
+            let node = Node { x, y };
+            simulation.snake.nodes.push_back(node);
+            simulation.grid.add_snake(node);
+        }
+        simulation.snake.direction = direction;
+    }
+
+    fn place_food(simulation: &mut Simulation, x: i32, y: i32) {
+        let cell = Node { x, y };
+        simulation.foods.push(Food {
+            food_type: FoodType::Normal,
+            cell,
+            expires_at: None,
+        });
+        simulation.grid.set_food(cell, true);
+    }
+
+    #[test]
+    fn biting_the_body_ends_the_game() {
+        let mut simulation = Simulation::new(empty_config());
+        place_snake(
+            &mut simulation,
+            &[(5, 5), (6, 5), (6, 4), (5, 4), (4, 4)],
+            Direction::Up,
+        );
+
+        simulation.step();
+
+        assert!(!simulation.snake.is_alive);
+        assert_eq!(
+            simulation.death_cause,
+            Some(DeathCause::SelfCollision {
+                segment: 4,
+                cell: Node { x: 5, y: 4 },
+            })
+        );
+    }
+
+    #[test]
+    fn ghost_passes_through_the_body_and_keeps_it_on_the_grid() {
+        let mut simulation = Simulation::new(empty_config());
+        place_snake(
+            &mut simulation,
+            &[(5, 5), (6, 5), (6, 4), (5, 4), (4, 4), (3, 4)],
+            Direction::Up,
+        );
+        simulation.snake.add_effect(PowerUpType::Ghost, 10);
+
+        simulation.step();
+        assert!(simulation.snake.is_alive);
+
+        // The body segment that was passed through leaves, the neck stays
+        simulation.step();
+        simulation.step();
+        assert!(simulation.snake.nodes.contains(&Node { x: 5, y: 4 }));
+        assert!(simulation.grid.is_snake(Node { x: 5, y: 4 }));
+        assert!(!simulation.grid.is_snake(Node { x: 4, y: 4 }));
+    }
+
+    #[test]
+    fn hitting_an_obstacle_ends_the_game() {
+        let mut simulation = Simulation::new(empty_config());
+        let head = *simulation.snake.nodes.front().unwrap();
+        let obstacle = Node {
+            x: head.x - 1,
+            y: head.y,
+        };
+        simulation.obstacles.push(obstacle);
+        simulation.grid.set_obstacle(obstacle, true);
+
+        simulation.step();
+
+        assert!(!simulation.snake.is_alive);
+        assert_eq!(
+            simulation.death_cause,
+            Some(DeathCause::Obstacle { cell: obstacle })
+        );
+    }
+
+    #[test]
+    fn shield_breaks_the_obstacle() {
+        let mut simulation = Simulation::new(empty_config());
+        let head = *simulation.snake.nodes.front().unwrap();
+        let obstacle = Node {
+            x: head.x - 1,
+            y: head.y,
+        };
+        simulation.obstacles.push(obstacle);
+        simulation.grid.set_obstacle(obstacle, true);
+        simulation.snake.add_effect(PowerUpType::Shield, 10);
+
+        simulation.step();
+
+        assert!(simulation.snake.is_alive);
+        assert!(simulation.obstacles.is_empty());
+        assert!(!simulation.grid.is_obstacle(obstacle));
+        assert!(!simulation.snake.has_effect(PowerUpType::Shield));
+    }
+
+    #[test]
+    fn hitting_a_wall_ends_the_game() {
+        let mut config = empty_config();
+        config.edge_x = EdgeMode::Wall;
+        let mut simulation = Simulation::new(config);
+        place_snake(&mut simulation, &[(0, 5), (1, 5), (2, 5)], Direction::Left);
+
+        simulation.step();
+
+        assert!(!simulation.snake.is_alive);
+        assert_eq!(
+            simulation.death_cause,
+            Some(DeathCause::Wall {
+                cell: Node { x: 0, y: 5 }
+            })
+        );
+    }
+
+    #[test]
+    fn wrapping_edges_move_the_head_to_the_other_side() {
+        let mut simulation = Simulation::new(empty_config());
+        place_snake(&mut simulation, &[(0, 5), (1, 5), (2, 5)], Direction::Left);
+
+        simulation.step();
+
+        assert!(simulation.snake.is_alive);
+        assert_eq!(
+            simulation.snake.nodes.front(),
+            Some(&Node {
+                x: simulation.config.grid_w() - 1,
+                y: 5
+            })
+        );
+    }
+
+    #[test]
+    fn filling_the_board_wins_the_game() {
+        let mut config = empty_config();
+        config.screen_w = 100.0;
+        config.screen_h = 25.0;
+        config.cell_w = 25.0;
+        config.hud_position = HudPosition::Hidden;
+        config.foods.count = 1;
+
+        // The snake takes 3 of the 4 cells, so the food is always on the
+        // last one, and the snake fills the row after eating twice
+        let mut simulation = Simulation::new(config);
+        assert_eq!(simulation.foods.len(), 1);
+        assert_eq!(simulation.grid.free_count(), 0);
+
+        while simulation.snake.is_alive && simulation.tick < 10 {
+            simulation.step();
+        }
+
+        assert!(simulation.has_won);
+        assert_eq!(simulation.tick, 2);
+        assert_eq!(simulation.death_cause, None);
+        assert_eq!(simulation.wins, 1);
+        assert_eq!(simulation.score, 2);
+    }
+
+    #[test]
+    fn a_pickup_on_the_last_cell_does_not_win_the_game() {
+        let mut config = Config::new();
+        config.seed = Some(3);
+        config.random_obstacle_count = 0;
+        config.screen_w = 125.0;
+        config.screen_h = 25.0;
+        config.power_ups.spawn_chance = 1.0;
+
+        // The pickup takes the cell the food would go on after eating, so
+        // the row is only full once the snake grew onto it
+        let mut simulation = Simulation::new(config);
+        let mut was_blocked = false;
+        while simulation.snake.is_alive && simulation.tick < 10 {
+            simulation.step();
+            was_blocked |= simulation.foods.is_empty() && !simulation.power_ups.is_empty();
+            assert!(!simulation.has_won || simulation.power_ups.is_empty());
+        }
+
+        assert!(was_blocked);
+        assert!(simulation.has_won);
+        assert_eq!(simulation.death_cause, None);
+        assert_eq!(simulation.snake.nodes.len(), 5);
+        assert_eq!(simulation.grid.free_count(), 0);
+    }
+
+    #[test]
+    fn score_saturates_instead_of_overflowing() {
+        let mut config = empty_config();
+        config.foods.normal.points = MAX_POINTS;
+        let mut simulation = Simulation::new(config);
+        let head = *simulation.snake.nodes.front().unwrap();
+        place_food(&mut simulation, head.x - 1, head.y);
+        simulation.snake.is_turbo = true;
+        simulation.score = u16::MAX - 1;
+
+        simulation.step();
+
+        assert_eq!(simulation.score, u16::MAX);
+    }
+
+    #[test]
+    fn update_runs_a_step_for_every_elapsed_delay() {
+        let mut simulation = Simulation::new(empty_config());
+        let movement_delay = simulation.movement_delay();
+
+        simulation.update(movement_delay * 3.5);
+
+        assert_eq!(simulation.tick, 3);
+        assert!((simulation.snake.last_movement_duration - movement_delay * 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn replay_reproduces_the_game() {
+        let mut config = Config::new();
+        config.seed = Some(42);
+        let mut simulation = Simulation::new(config);
+
+        let inputs = [
+            (2, Input::Direction(Direction::Up)),
+            (5, Input::Turbo(true)),
+            (6, Input::Direction(Direction::Right)),
+            (11, Input::Direction(Direction::Down)),
+            (12, Input::Turbo(false)),
+            (17, Input::Direction(Direction::Left)),
+        ];
+        while simulation.snake.is_alive && simulation.tick < 30 {
+            for (tick, input) in inputs.iter() {
+                if *tick == simulation.tick {
+                    simulation.apply_input(*input);
+                }
+            }
+            simulation.step();
+        }
+
+        // Go through the file, so the saved form is covered too
+        let path =
+            std::env::temp_dir().join(format!("snake_replay_test_{}.json", std::process::id()));
+        simulation.recording.save(&path).unwrap();
+        let replay = Replay::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let mut replayed = Simulation::from_replay(replay);
+        while replayed.tick < simulation.tick {
+            replayed.step();
+        }
+
+        assert_eq!(replayed.seed, simulation.seed);
+        assert_eq!(replayed.snake.nodes, simulation.snake.nodes);
+        assert_eq!(replayed.snake.is_alive, simulation.snake.is_alive);
+        assert_eq!(replayed.foods, simulation.foods);
+        assert_eq!(replayed.obstacles, simulation.obstacles);
+        assert_eq!(replayed.score, simulation.score);
+        assert_eq!(replayed.death_cause, simulation.death_cause);
+    }
+}
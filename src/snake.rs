@@ -1,30 +1,36 @@
-use crate::config::Config;
-use std::collections::LinkedList;
+use crate::config::{Config, EdgeMode, PowerUpType};
+use std::collections::VecDeque;
 
-type Milliseconds = f64;
+pub type Milliseconds = f64;
 
 pub struct Snake {
-    pub nodes: LinkedList<Node>,
+    pub nodes: VecDeque<Node>,
     pub direction: Direction,
     pub is_alive: bool,
     pub is_turbo: bool,
     pub movement_delay: Milliseconds,
     pub last_movement_duration: Milliseconds,
+    /// The number of upcoming moves the tail should stay in place for.
+    pub pending_growth: u32,
+    /// The power-up effects that are active right now.
+    pub effects: Vec<Effect>,
 }
 
-#[derive(Clone, Debug, Copy)]
-pub struct Node {
-    pub x: f64,
-    pub y: f64,
+/// A power-up effect on the snake, which runs out after a number of moves.
+#[derive(Clone, Debug, Copy, PartialEq)]
+pub struct Effect {
+    pub power_up_type: PowerUpType,
+    pub moves_left: u64,
 }
 
-impl Node {
-    pub fn eq(&self, other_node: Node) -> bool {
-        return self.x == other_node.x && self.y == other_node.y;
-    }
+/// A cell on the grid, in cell coordinates.
+#[derive(Clone, Debug, Copy, PartialEq, Eq)]
+pub struct Node {
+    pub x: i32,
+    pub y: i32,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Copy, PartialEq)]
 pub enum Direction {
     Up,
     Down,
@@ -32,37 +38,121 @@ pub enum Direction {
     Left,
 }
 
-impl Snake {
-    pub fn update_node_locations(self: &mut Snake, x_change: f64, y_change: f64, config: Config) {
-        let mut clone_nodes = self.nodes.clone();
-        let mut iter = clone_nodes.iter_mut();
-        let mut prev = iter.next().unwrap().clone();
-
-        while let Some(current) = iter.next() {
-            let temp = current.clone();
-            current.x = prev.x;
-            current.y = prev.y;
-            prev = temp;
+impl Direction {
+    /// The change of the cell coordinates for a move in this direction.
+    pub fn offset(self) -> (i32, i32) {
+        match self {
+            Direction::Up => (0, -1),
+            Direction::Down => (0, 1),
+            Direction::Right => (1, 0),
+            Direction::Left => (-1, 0),
         }
+    }
+}
 
-        iter = clone_nodes.iter_mut();
+impl Snake {
+    /// Moves the snake by a single cell, returning the new head and the cell
+    /// the tail has left, if it did move. Returns `None` when the snake ran
+    /// into a wall, leaving it where it was.
+    pub fn update_node_locations(self: &mut Snake, config: Config) -> Option<(Node, Option<Node>)> {
+        let head = match self.next_head(config) {
+            Ok(head) => head,
+            // The snake is stuck if the way back is blocked by an edge too
+            Err(EdgeMode::Bounce) => {
+                self.turn_around();
+                self.next_head(config).ok()?
+            }
+            Err(_) => return None,
+        };
+
+        // Keep the tail in place while growing
+        let left_tail = if self.pending_growth > 0 {
+            self.pending_growth -= 1;
+            None
+        } else {
+            self.nodes.pop_back()
+        };
+
+        self.nodes.push_front(head);
+
+        Some((head, left_tail))
+    }
+
+    pub fn has_effect(&self, power_up_type: PowerUpType) -> bool {
+        self.effects
+            .iter()
+            .any(|effect| effect.power_up_type == power_up_type)
+    }
 
-        let head = iter.next().unwrap();
+    /// Starts the effect, or restarts it if it is already active.
+    pub fn add_effect(&mut self, power_up_type: PowerUpType, duration: u64) {
+        self.remove_effect(power_up_type);
+        self.effects.push(Effect {
+            power_up_type,
+            moves_left: duration,
+        });
+    }
+
+    pub fn remove_effect(&mut self, power_up_type: PowerUpType) {
+        self.effects
+            .retain(|effect| effect.power_up_type != power_up_type);
+    }
+
+    /// Counts down the effects by a single move, dropping the ones that
+    /// have run out.
+    pub fn tick_effects(&mut self) {
+        for effect in self.effects.iter_mut() {
+            effect.moves_left = effect.moves_left.saturating_sub(1);
+        }
+        self.effects.retain(|effect| effect.moves_left > 0);
+    }
+
+    /// Returns the cell in front of the head, or the rule of the edge
+    /// that is in the way.
+    fn next_head(&self, config: Config) -> Result<Node, EdgeMode> {
+        let (x_change, y_change) = self.direction.offset();
+        let mut head = *self.nodes.front().unwrap();
         head.x += x_change;
         head.y += y_change;
 
-        if head.x == -1.0 {
-            head.x = (config.screen_w / config.cell_w) - 1.0;
-        } else if head.x == config.screen_w / config.cell_w {
-            head.x = 0.0;
+        if head.x < 0 || head.x >= config.grid_w() {
+            match config.edge_x {
+                EdgeMode::Wrap => head.x = head.x.rem_euclid(config.grid_w()),
+                edge_mode => return Err(edge_mode),
+            }
         }
 
-        if head.y == -1.0 {
-            head.y = (config.screen_h / config.cell_w) - 1.0;
-        } else if head.y == config.screen_h / config.cell_w {
-            head.y = 0.0;
+        if head.y < 0 || head.y >= config.grid_h() {
+            match config.edge_y {
+                EdgeMode::Wrap => head.y = head.y.rem_euclid(config.grid_h()),
+                edge_mode => return Err(edge_mode),
+            }
         }
 
-        self.nodes = clone_nodes;
+        Ok(head)
+    }
+
+    /// Reverses the snake, so the tail becomes the head and leads the way
+    /// back along the body.
+    fn turn_around(&mut self) {
+        self.nodes.make_contiguous().reverse();
+
+        let (head, neck) = match (self.nodes.front(), self.nodes.get(1)) {
+            (Some(head), Some(neck)) => (*head, *neck),
+            _ => return,
+        };
+
+        // A step of more than a single cell went across a wrapping edge
+        let towards = |change: i32| match change.abs() > 1 {
+            true => -change.signum(),
+            false => change,
+        };
+
+        self.direction = match (towards(head.x - neck.x), towards(head.y - neck.y)) {
+            (1, _) => Direction::Right,
+            (-1, _) => Direction::Left,
+            (_, 1) => Direction::Down,
+            _ => Direction::Up,
+        };
     }
 }
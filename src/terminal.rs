@@ -0,0 +1,332 @@
+use crate::color::{blend, snake_node_color, Color};
+use crate::config::DifficultyCurve;
+use crate::scores::ScoreBoard;
+use crate::simulation::Simulation;
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::style::{self, Print, ResetColor, SetBackgroundColor, SetForegroundColor};
+use crossterm::{cursor, execute, queue, terminal};
+use piston::input::Key;
+use std::env::var;
+use std::io::{stdout, Write};
+use std::time::{Duration, Instant};
+
+const FRAME_DURATION: Duration = Duration::from_millis(16);
+
+/// Every cell is drawn with two characters, so the cells look square.
+const CELL: &str = "  ";
+
+/// The colors the terminal is able to show.
+#[derive(Clone, Debug, Copy, PartialEq)]
+pub enum ColorMode {
+    TrueColor,
+    Ansi256,
+}
+
+impl ColorMode {
+    /// Uses true colors if the terminal says it supports them.
+    pub fn detect() -> Self {
+        match var("COLORTERM").as_deref() {
+            Ok("truecolor") | Ok("24bit") => ColorMode::TrueColor,
+            _ => ColorMode::Ansi256,
+        }
+    }
+}
+
+/// Plays the game in the terminal until q is pressed on the pause or
+/// game over screen.
+pub fn run_terminal(mut simulation: Simulation) -> Result<(), Box<dyn std::error::Error>> {
+    let mut scores = ScoreBoard::load();
+    simulation.high_score = scores.high_score(&simulation.config);
+
+    let color_mode = ColorMode::detect();
+    let mut stdout = stdout();
+
+    terminal::enable_raw_mode()?;
+    execute!(
+        stdout,
+        terminal::EnterAlternateScreen,
+        cursor::Hide,
+        event::EnableFocusChange
+    )?;
+
+    let result = game_loop(&mut simulation, &mut scores, color_mode, &mut stdout);
+
+    // Always give the terminal back, even if the game loop failed
+    execute!(
+        stdout,
+        event::DisableFocusChange,
+        ResetColor,
+        cursor::Show,
+        terminal::LeaveAlternateScreen
+    )?;
+    terminal::disable_raw_mode()?;
+
+    result
+}
+
+fn game_loop<W: Write>(
+    simulation: &mut Simulation,
+    scores: &mut ScoreBoard,
+    color_mode: ColorMode,
+    out: &mut W,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut last_update = Instant::now();
+    let mut needs_redraw = true;
+
+    loop {
+        if needs_redraw {
+            queue!(out, cursor::MoveTo(0, 0))?;
+            draw_frame(out, simulation, color_mode, true)?;
+            out.flush()?;
+            needs_redraw = false;
+        }
+
+        if event::poll(FRAME_DURATION)? {
+            match event::read()? {
+                Event::Key(key_event) if key_event.kind != KeyEventKind::Release => {
+                    match key_event.code {
+                        // Quitting is only possible from the pause or game over screen
+                        KeyCode::Char('q') => match simulation.snake.is_alive {
+                            true if simulation.is_paused => return Ok(()),
+                            true => simulation.pause(),
+                            false => return Ok(()),
+                        },
+                        KeyCode::Char('c') if key_event.modifiers == KeyModifiers::CONTROL => {
+                            return Ok(())
+                        }
+                        // Terminals don't report held keys, so turbo is toggled instead
+                        KeyCode::Char('t') => match simulation.snake.is_turbo {
+                            true => simulation.handle_key_release(Key::LShift),
+                            false => simulation.handle_key_press(Key::LShift),
+                        },
+                        code => {
+                            if let Some(key) = to_piston_key(code) {
+                                simulation.handle_key_press(key);
+                            }
+                        }
+                    }
+                    needs_redraw = true;
+                }
+                Event::FocusLost => {
+                    simulation.pause();
+                    needs_redraw = true;
+                }
+                Event::Resize(_, _) => {
+                    queue!(out, terminal::Clear(terminal::ClearType::All))?;
+                    needs_redraw = true;
+                }
+                _ => {}
+            }
+        }
+
+        let now = Instant::now();
+        let dt_ms = now.duration_since(last_update).as_secs_f64() * 1000.0;
+        last_update = now;
+
+        let was_alive = simulation.snake.is_alive;
+        let tick = simulation.tick;
+        simulation.update(dt_ms);
+
+        if simulation.tick != tick {
+            needs_redraw = true;
+        }
+
+        if was_alive && !simulation.snake.is_alive && !simulation.is_replay() {
+            scores.record(
+                &simulation.config,
+                simulation.score,
+                simulation.seed,
+                simulation.has_won,
+            );
+            // There is no place to show the error while the game is on screen
+            let _ = scores.save();
+            simulation.high_score = scores.high_score(&simulation.config);
+        }
+    }
+}
+
+/// Writes the score line and the board starting at the current cursor
+/// position, one line per row of cells. Interactive frames also clear
+/// what is left of the previous frame and show the key prompts.
+pub fn draw_frame<W: Write>(
+    out: &mut W,
+    simulation: &Simulation,
+    color_mode: ColorMode,
+    is_interactive: bool,
+) -> std::io::Result<()> {
+    let config = &simulation.config;
+    let text_color = to_terminal_color(config.text_color, config.background_color, color_mode);
+
+    let mut status = format!(
+        "Score: {}  High Score: {}",
+        simulation.score, simulation.high_score
+    );
+    if simulation.wins > 0 {
+        status.push_str(format!("  Wins: {}", simulation.wins).as_str());
+    }
+    if config.difficulty.curve != DifficultyCurve::Constant {
+        status.push_str(format!("  Level: {}", simulation.level()).as_str());
+    }
+    if let Some(expires_at) = simulation.foods.iter().find_map(|food| food.expires_at) {
+        status
+            .push_str(format!("  Bonus: {}", expires_at.saturating_sub(simulation.tick)).as_str());
+    }
+    for effect in simulation.snake.effects.iter() {
+        status.push_str(
+            format!("  {}: {}", effect.power_up_type.label(), effect.moves_left).as_str(),
+        );
+    }
+    if simulation.snake.is_turbo {
+        status.push_str("  Turbo");
+    }
+    if simulation.is_replay() {
+        status.push_str("  Replay");
+    }
+
+    queue!(out, SetForegroundColor(text_color), Print(status))?;
+    end_line(out, is_interactive)?;
+
+    let grid_w = config.grid_w();
+    let grid_h = config.grid_h();
+
+    // Paint the cells back to front, the same way the window does
+    let mut cells: Vec<Color> = vec![config.background_color; (grid_w * grid_h) as usize];
+    let mut paint = |x: i32, y: i32, color: Color| {
+        if x >= 0 && y >= 0 && x < grid_w && y < grid_h {
+            let index = (y * grid_w + x) as usize;
+            cells[index] = blend(color, cells[index]);
+        }
+    };
+
+    for food in simulation.foods.iter() {
+        paint(
+            food.cell.x,
+            food.cell.y,
+            config.color_of_food(food.food_type),
+        );
+    }
+
+    let mut node_index = 1.0;
+    for node in simulation.snake.nodes.iter().rev() {
+        let color = snake_node_color(
+            config,
+            simulation.snake.is_turbo,
+            simulation.snake.nodes.len(),
+            node_index,
+        );
+        paint(node.x, node.y, color);
+        node_index += 1.0;
+    }
+
+    if let Some(head) = simulation.snake.nodes.front() {
+        paint(head.x, head.y, config.snake_head_color);
+    }
+
+    for power_up in simulation.power_ups.iter() {
+        paint(
+            power_up.cell.x,
+            power_up.cell.y,
+            config.color_of_power_up(power_up.power_up_type),
+        );
+    }
+
+    for obstacle in simulation.obstacles.iter() {
+        paint(obstacle.x, obstacle.y, config.obstacle_color);
+    }
+
+    if let Some(cell) = simulation.death_cause.and_then(|cause| cause.cell()) {
+        paint(cell.x, cell.y, config.fatal_cell_color);
+    }
+
+    for row in cells.chunks(grid_w.max(1) as usize) {
+        let mut current_color = None;
+        for cell in row {
+            let color = to_terminal_color(*cell, config.background_color, color_mode);
+            if current_color != Some(color) {
+                queue!(out, SetBackgroundColor(color))?;
+                current_color = Some(color);
+            }
+            queue!(out, Print(CELL))?;
+        }
+        end_line(out, is_interactive)?;
+    }
+
+    // Leave out the prompts and the cursor handling when the frame is
+    // only printed, e.g. by a headless run
+    if !is_interactive {
+        return Ok(());
+    }
+
+    if simulation.is_paused {
+        queue!(
+            out,
+            SetForegroundColor(text_color),
+            Print("Paused\r\n"),
+            Print("Press p or Esc to resume, q to quit\r\n"),
+            ResetColor
+        )?;
+    }
+
+    if !simulation.snake.is_alive {
+        let game_over_text = match (simulation.has_won, simulation.death_cause) {
+            (true, _) => String::from("You Win!"),
+            (false, Some(death_cause)) => format!("Game Over! {}.", death_cause),
+            (false, None) => String::from("Game Over!"),
+        };
+
+        queue!(
+            out,
+            SetForegroundColor(text_color),
+            Print(format!(
+                "{} Score: {}  Seed: {}\r\n",
+                game_over_text, simulation.score, simulation.seed
+            )),
+            Print("Press space to restart or q to quit\r\n"),
+            ResetColor
+        )?;
+    }
+
+    queue!(out, terminal::Clear(terminal::ClearType::FromCursorDown))
+}
+
+/// Ends the current line, clearing the rest of it on screen when the
+/// frame is drawn over the previous one.
+fn end_line<W: Write>(out: &mut W, is_interactive: bool) -> std::io::Result<()> {
+    match is_interactive {
+        true => queue!(
+            out,
+            ResetColor,
+            terminal::Clear(terminal::ClearType::UntilNewLine),
+            Print("\r\n")
+        ),
+        false => queue!(out, ResetColor, Print("\n")),
+    }
+}
+
+fn to_terminal_color(color: Color, background: Color, color_mode: ColorMode) -> style::Color {
+    let color = blend(color, background);
+    let to_byte = |channel: f32| (channel.clamp(0.0, 1.0) * 255.0).round() as u8;
+    let (r, g, b) = (to_byte(color[0]), to_byte(color[1]), to_byte(color[2]));
+
+    match color_mode {
+        ColorMode::TrueColor => style::Color::Rgb { r, g, b },
+        ColorMode::Ansi256 => {
+            // Pick the closest color of the 6x6x6 color cube
+            let to_level = |channel: u8| ((channel as u16 * 5 + 127) / 255) as u8;
+            style::Color::AnsiValue(16 + 36 * to_level(r) + 6 * to_level(g) + to_level(b))
+        }
+    }
+}
+
+fn to_piston_key(code: KeyCode) -> Option<Key> {
+    match code {
+        KeyCode::Up | KeyCode::Char('w') => Some(Key::Up),
+        KeyCode::Down | KeyCode::Char('s') => Some(Key::Down),
+        KeyCode::Right | KeyCode::Char('d') => Some(Key::Right),
+        KeyCode::Left | KeyCode::Char('a') => Some(Key::Left),
+        KeyCode::Char(' ') => Some(Key::Space),
+        KeyCode::Char('p') => Some(Key::P),
+        KeyCode::Esc => Some(Key::Escape),
+        _ => None,
+    }
+}
@@ -0,0 +1,187 @@
+use crate::color::{deserialize_color, Color};
+use crate::config::{config_dir, Config, ConfigError, FieldError};
+use std::env::current_exe;
+use std::fs::{read_dir, read_to_string};
+use std::path::PathBuf;
+
+/// The themes shipped in the binary, by name.
+const BUILT_IN_THEMES: [(&str, &str); 4] = [
+    ("default", include_str!("../assets/themes/default.json")),
+    ("classic", include_str!("../assets/themes/classic.json")),
+    ("ocean", include_str!("../assets/themes/ocean.json")),
+    ("paper", include_str!("../assets/themes/paper.json")),
+];
+
+/// A named set of colors. The colors a theme leaves out are the defaults.
+#[derive(Clone, Debug)]
+pub struct Theme {
+    pub name: String,
+    colors: Vec<(&'static str, Color)>,
+}
+
+impl Theme {
+    /// Loads a theme by name from the theme directories, falling back to
+    /// the built-in themes, so those can be overridden by a file.
+    pub fn load(name: &str) -> Result<Theme, ConfigError> {
+        for theme_dir in theme_dirs() {
+            let theme_path = theme_dir.join(format!("{}.json", name));
+            if !theme_path.is_file() {
+                continue;
+            }
+
+            let with_file = |mut e: ConfigError| {
+                e.file = Some(theme_path.clone());
+                e
+            };
+
+            let file_content = read_to_string(&theme_path).map_err(|e| {
+                with_file(ConfigError::new(vec![FieldError::new("$", e.to_string())]))
+            })?;
+
+            return Self::from_str(name, file_content.as_str()).map_err(with_file);
+        }
+
+        match BUILT_IN_THEMES
+            .iter()
+            .find(|(theme_name, _)| *theme_name == name)
+        {
+            Some((_, content)) => Self::from_str(name, content),
+            None => Err(ConfigError::new(vec![FieldError::new(
+                "$.theme",
+                format!(
+                    "unknown theme \"{}\", available themes: {}",
+                    name,
+                    theme_names().join(", ")
+                ),
+            )])),
+        }
+    }
+
+    /// Parses a theme from json text, where every key is one of the colors
+    /// of the config. Every invalid color is reported in the error.
+    pub fn from_str(name: &str, content: &str) -> Result<Theme, ConfigError> {
+        let json: serde_json::Value = serde_json::from_str(content)
+            .map_err(|e| ConfigError::new(vec![FieldError::new("$", e.to_string())]))?;
+        let object = json.as_object().ok_or_else(|| {
+            ConfigError::new(vec![FieldError::new("$", "expected a json object")])
+        })?;
+
+        let mut colors = Vec::new();
+        let mut errors = Vec::new();
+        for (key, value) in object {
+            let color_name = match Config::new()
+                .colors()
+                .iter()
+                .find(|(color_name, _)| color_name == key)
+            {
+                Some((color_name, _)) => *color_name,
+                None => {
+                    eprintln!("Ignoring unknown theme key \"{}\"", key);
+                    continue;
+                }
+            };
+
+            match deserialize_color(value.clone()) {
+                Ok(color)
+                    if color
+                        .iter()
+                        .all(|component| (0.0..=1.0).contains(component)) =>
+                {
+                    colors.push((color_name, color))
+                }
+                Ok(_) => errors.push(FieldError::new(
+                    &format!("$.{}", key),
+                    "the components should be between 0 and 1",
+                )),
+                Err(e) => errors.push(FieldError::new(&format!("$.{}", key), e.to_string())),
+            }
+        }
+
+        if !errors.is_empty() {
+            return Err(ConfigError::new(errors));
+        }
+
+        Ok(Theme {
+            name: String::from(name),
+            colors,
+        })
+    }
+
+    /// Sets every color of the config to the one of the theme.
+    pub fn apply(&self, config: &mut Config) {
+        for (name, default_color) in Config::new().colors() {
+            let color = self
+                .colors
+                .iter()
+                .find(|(color_name, _)| *color_name == name)
+                .map(|(_, color)| *color)
+                .unwrap_or(default_color);
+
+            if let Some(config_color) = config.color_mut(name) {
+                *config_color = color;
+            }
+        }
+    }
+}
+
+/// Returns the names of the built-in themes and of the theme files,
+/// sorted and without duplicates.
+pub fn theme_names() -> Vec<String> {
+    let mut names: Vec<String> = BUILT_IN_THEMES
+        .iter()
+        .map(|(name, _)| String::from(*name))
+        .collect();
+
+    for theme_dir in theme_dirs() {
+        let entries = match read_dir(theme_dir) {
+            Ok(entries) => entries,
+            Err(_) => continue,
+        };
+
+        for path in entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+        {
+            if path
+                .extension()
+                .is_some_and(|extension| extension == "json")
+            {
+                if let Some(name) = path.file_stem().and_then(|name| name.to_str()) {
+                    names.push(String::from(name));
+                }
+            }
+        }
+    }
+
+    names.sort();
+    names.dedup();
+    names
+}
+
+/// Returns the name of the theme after the given one, wrapping around.
+pub fn next_theme_name(current: Option<&str>) -> String {
+    let names = theme_names();
+    let next_index = current
+        .and_then(|current| names.iter().position(|name| name == current))
+        .map_or(0, |index| (index + 1) % names.len());
+
+    names[next_index].clone()
+}
+
+/// The directories theme files are looked up in: `themes` in the config
+/// directory and next to the executable.
+fn theme_dirs() -> Vec<PathBuf> {
+    let mut theme_dirs = Vec::new();
+
+    if let Some(config_dir) = config_dir() {
+        theme_dirs.push(config_dir.join("themes"));
+    }
+
+    if let Ok(mut exe_path) = current_exe() {
+        exe_path.pop();
+        exe_path.push("themes");
+        theme_dirs.push(exe_path);
+    }
+
+    theme_dirs
+}
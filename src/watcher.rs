@@ -0,0 +1,79 @@
+use crate::cli::Cli;
+use crate::config::{Config, ConfigError};
+use crate::snake::Milliseconds;
+use std::fs::metadata;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// How often the config file is checked for changes.
+const POLL_INTERVAL: Milliseconds = 500.0;
+
+/// Watches the config file by polling its modification time, so the
+/// changes can be applied while the game is running.
+pub struct ConfigWatcher {
+    pub path: PathBuf,
+    /// The command line overrides, which still apply on top of the file.
+    cli: Cli,
+    modified: Option<SystemTime>,
+    since_last_poll: Milliseconds,
+}
+
+impl ConfigWatcher {
+    pub fn new(path: PathBuf, cli: Cli) -> Self {
+        let modified = modified_time(&path);
+
+        ConfigWatcher {
+            path,
+            cli,
+            modified,
+            since_last_poll: 0.0,
+        }
+    }
+
+    /// Returns the reloaded config once the file has changed.
+    pub fn poll(&mut self, dt_ms: Milliseconds) -> Option<Result<Config, ConfigError>> {
+        self.since_last_poll += dt_ms;
+        if self.since_last_poll < POLL_INTERVAL {
+            return None;
+        }
+        self.since_last_poll = 0.0;
+
+        let modified = modified_time(&self.path);
+        if modified == self.modified {
+            return None;
+        }
+        self.modified = modified;
+
+        // Keep the current config if the file was removed
+        modified?;
+
+        Some(
+            Config::load_config_file(Some(self.path.clone()))
+                .and_then(|config| self.cli.apply_overrides(config)),
+        )
+    }
+
+    /// Loads the config as it is in the file, without the command line
+    /// overrides, so editing it doesn't make them permanent.
+    pub fn load_file(&self) -> Result<Config, ConfigError> {
+        match self.path.is_file() {
+            true => Config::load_config_file(Some(self.path.clone())),
+            false => Ok(Config::new()),
+        }
+    }
+
+    /// Applies the command line overrides on top of a config from the file.
+    pub fn apply_overrides(&self, config: Config) -> Result<Config, ConfigError> {
+        self.cli.apply_overrides(config)
+    }
+
+    /// Takes the current state of the file as seen, e.g. after the game
+    /// has written it itself.
+    pub fn mark_seen(&mut self) {
+        self.modified = modified_time(&self.path);
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    metadata(path).and_then(|metadata| metadata.modified()).ok()
+}